@@ -208,6 +208,18 @@ def run_database_benchmarks():
         
         benchmark("Query 100 rows", query_many_rows, iterations=500)
         
+        # Benchmark 8: Wide rows as dicts vs tuples
+        wide_sql = "SELECT " + ", ".join(f"g + {i} as c{i}" for i in range(30)) + " FROM generate_series(1, 1000) g"
+        
+        def query_wide_dicts():
+            return len(pool.query(wide_sql))
+        
+        def query_wide_tuples():
+            return len(pool.query(wide_sql, row_factory="tuple"))
+        
+        benchmark("Query 1000 x 30 columns (dicts)", query_wide_dicts, iterations=100)
+        benchmark("Query 1000 x 30 columns (tuples)", query_wide_tuples, iterations=100)
+        
         # Cleanup
         pool.execute_raw("DROP TABLE IF EXISTS benchmark_test")
         pool.close()
@@ -32,22 +32,58 @@ Example usage:
 """
 
 from db_connector._internal import (
+    AdvisoryLock,
     SslMode,
     ConnectionConfig,
     AsyncPool,
+    CancelToken,
     Connection,
+    Cursor,
+    FailoverPool,
+    Null,
+    PooledConnection,
+    RowIterator,
+    Transaction,
     create_pool,
     connect,
     connect_url,
+    expand_in,
+    init_tracing,
+    quote_identifier,
+    quote_literal,
+    register_adapter,
+    register_converter,
+    register_enum,
 )
 
 __version__ = "0.1.0"
+
+# DB-API 2.0 module globals (see Connection.cursor)
+apilevel = "2.0"
+threadsafety = 1
+paramstyle = "pyformat"
+
 __all__ = [
+    "AdvisoryLock",
     "SslMode",
     "ConnectionConfig",
     "AsyncPool", 
+    "CancelToken",
     "Connection",
+    "Cursor",
+    "FailoverPool",
+    "Null",
+    "PooledConnection",
+    "RowIterator",
+    "Transaction",
     "create_pool",
     "connect",
     "connect_url",
+    "expand_in",
+    "init_tracing",
+    "quote_identifier",
+    "quote_literal",
+    "register_adapter",
+    "register_converter",
+    "register_enum",
 ]
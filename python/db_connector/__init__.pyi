@@ -4,14 +4,28 @@ Type stubs for db_connector
 These type hints enable IDE autocompletion and type checking.
 """
 
-from typing import Any, Dict, List, Optional, Tuple, Union
+from datetime import datetime, timedelta
+from typing import Any, Callable, Dict, Iterable, List, Mapping, Optional, Sequence, Tuple, TypeVar, Union
 from enum import IntEnum
 
+T = TypeVar("T")
+
 class SslMode(IntEnum):
     """SSL connection modes."""
     Disable = 0
     Prefer = 1
     Require = 2
+    VerifyCa = 3
+    VerifyFull = 4
+
+    @staticmethod
+    def from_str(value: str) -> "SslMode":
+        """Mode for a libpq sslmode name: disable, allow, prefer, require, verify-ca or verify-full."""
+        ...
+
+    def __str__(self) -> str:
+        """The libpq sslmode name, e.g. "require"."""
+        ...
 
 class ConnectionConfig:
     """Database connection configuration."""
@@ -22,9 +36,36 @@ class ConnectionConfig:
     password: str
     database: str
     pool_size: int
+    pool_acquire_timeout_secs: int
     ssl_mode: SslMode
     connect_timeout_secs: int
     statement_timeout_secs: int
+    timestamp_output: str
+    statement_cache_size: int
+    client_cert_path: Optional[str]
+    client_key_path: Optional[str]
+    root_cert_path: Optional[str]
+    application_name: Optional[str]
+    options: Optional[str]
+    on_connect_sql: List[str]
+    auto_reconnect: bool
+    reconnect_attempts: int
+    reconnect_backoff_ms: int
+    connect_retries: int
+    connect_retry_backoff_ms: int
+    hosts: List[str]
+    target_session_attrs: str
+    keepalives: bool
+    keepalives_idle_secs: int
+    recycling_method: str
+    max_connection_lifetime_secs: int
+    slow_query_threshold_ms: int
+    prewarm: bool
+    bytea_as_memoryview: bool
+    error_sql_length: int
+    redact_error_sql: bool
+    default_fetch_size: int
+    server_statement_timeout: bool
     
     def __init__(
         self,
@@ -37,6 +78,33 @@ class ConnectionConfig:
         ssl_mode: SslMode = SslMode.Disable,
         connect_timeout_secs: int = 30,
         statement_timeout_secs: int = 30,
+        timestamp_output: str = "datetime",
+        statement_cache_size: int = 100,
+        pool_acquire_timeout_secs: int = 30,
+        client_cert_path: Optional[str] = None,
+        client_key_path: Optional[str] = None,
+        root_cert_path: Optional[str] = None,
+        application_name: Optional[str] = None,
+        options: Optional[str] = None,
+        on_connect_sql: List[str] = [],
+        auto_reconnect: bool = False,
+        reconnect_attempts: int = 3,
+        reconnect_backoff_ms: int = 200,
+        connect_retries: int = 0,
+        connect_retry_backoff_ms: int = 500,
+        hosts: List[str] = [],
+        target_session_attrs: str = "any",
+        keepalives: bool = True,
+        keepalives_idle_secs: int = 7200,
+        recycling_method: str = "fast",
+        max_connection_lifetime_secs: int = 0,
+        slow_query_threshold_ms: int = 0,
+        prewarm: bool = False,
+        bytea_as_memoryview: bool = False,
+        error_sql_length: int = 200,
+        redact_error_sql: bool = False,
+        default_fetch_size: int = 1000,
+        server_statement_timeout: bool = False,
     ) -> None: ...
     
     @staticmethod
@@ -44,12 +112,33 @@ class ConnectionConfig:
         """Create config from connection URL."""
         ...
     
+    @staticmethod
+    def from_env() -> "ConnectionConfig":
+        """Create config from DATABASE_URL, or the PGHOST/PGPORT/PGUSER/... variables."""
+        ...
+    
+    def to_url(self, mask_password: bool = True) -> str:
+        """Build a connection URL; the password is shown as *** unless mask_password is False."""
+        ...
+    
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> "ConnectionConfig": ...
+    def __deepcopy__(self, memo: Dict[int, Any]) -> "ConnectionConfig": ...
+    
+    def __getstate__(self) -> Dict[str, Any]: ...
+    def __setstate__(self, state: Dict[str, Any]) -> None: ...
+    
+    def validate(self) -> None:
+        """Raise ValueError for the first invalid setting (pool_size 0, empty host, port 0, ...)."""
+        ...
+    
     def with_pool_size(self, pool_size: int) -> "ConnectionConfig":
         """Return copy with modified pool size."""
         ...
     
-    def with_ssl(self, ssl_mode: SslMode) -> "ConnectionConfig":
-        """Return copy with modified SSL mode."""
+    def with_ssl(self, ssl_mode: Union[SslMode, str]) -> "ConnectionConfig":
+        """Return copy with modified SSL mode, an SslMode or a libpq name like "require"."""
         ...
     
     def with_timeouts(
@@ -60,31 +149,334 @@ class ConnectionConfig:
         """Return copy with modified timeouts."""
         ...
 
+class CancelToken:
+    """Handle for aborting a query_cancellable call from another thread."""
+    
+    def __init__(self) -> None: ...
+    
+    @property
+    def cancelled(self) -> bool:
+        """Whether cancel() has been called."""
+        ...
+    
+    def cancel(self) -> None:
+        """Send a cancel request for the running query (or fail it before it starts)."""
+        ...
+
+class Null:
+    """A NULL parameter with a declared type, e.g. Null("int4")."""
+    
+    def __init__(self, type_name: Union[str, int]) -> None: ...
+    
+    @property
+    def type_name(self) -> str:
+        """The declared type's name."""
+        ...
+    
+    @property
+    def oid(self) -> int:
+        """The declared type's OID."""
+        ...
+
+class Cursor:
+    """DB-API 2.0 cursor; placeholders are %s (sequence) or %(name)s (mapping)."""
+    
+    arraysize: int
+    
+    @property
+    def rowcount(self) -> int:
+        """Rows returned or affected by the last statement (-1 before the first)."""
+        ...
+    
+    @property
+    def description(self) -> Optional[List[Tuple[str, int, None, None, None, None, None]]]:
+        """(name, type_oid, None, None, None, None, None) per column, or None."""
+        ...
+    
+    @property
+    def closed(self) -> bool: ...
+    
+    def execute(self, sql: str, params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None) -> None:
+        """Run a statement; %s/%(name)s are rewritten to $n when params are given."""
+        ...
+    
+    def executemany(self, sql: str, seq_of_params: Iterable[Union[Sequence[Any], Mapping[str, Any]]]) -> None:
+        """Run a statement once per parameter set."""
+        ...
+    
+    def fetchone(self) -> Optional[Tuple[Any, ...]]: ...
+    def fetchmany(self, size: Optional[int] = None) -> List[Tuple[Any, ...]]: ...
+    def fetchall(self) -> List[Tuple[Any, ...]]: ...
+    def close(self) -> None: ...
+    def setinputsizes(self, sizes: Any) -> None: ...
+    def setoutputsize(self, size: Any, column: Optional[int] = None) -> None: ...
+    def __iter__(self) -> "Cursor": ...
+    def __next__(self) -> Tuple[Any, ...]: ...
+    def __enter__(self) -> "Cursor": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
+class RowIterator:
+    """Rows of AsyncPool.iter_rows(), fetched lazily through a server-side cursor."""
+    
+    fetch_size: int
+    """Rows fetched per round trip; a new size applies from the next fetch."""
+    
+    @property
+    def closed(self) -> bool:
+        """Whether the connection has been returned to the pool."""
+        ...
+    
+    def close(self) -> None:
+        """Stop iterating and return the connection to the pool."""
+        ...
+    
+    def __iter__(self) -> "RowIterator": ...
+    def __next__(self) -> Dict[str, Any]: ...
+    def __enter__(self) -> "RowIterator": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
+class AdvisoryLock:
+    """A held advisory lock from advisory_lock(); released by release() or a with block."""
+    
+    @property
+    def held(self) -> bool:
+        """Whether the lock hasn't been released through this object."""
+        ...
+    
+    def release(self) -> None:
+        """Release the lock; a no-op if already released."""
+        ...
+    
+    def __enter__(self) -> "AdvisoryLock": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
+class Transaction:
+    """A transaction from transaction(): committed when its with block ends, rolled back if it raises."""
+    
+    @property
+    def read_only(self) -> bool:
+        """Whether it begins as READ ONLY, so writes fail at the server."""
+        ...
+    
+    @property
+    def isolation_level(self) -> Optional[str]:
+        """The isolation level it begins with; None keeps the session default."""
+        ...
+    
+    def __enter__(self) -> "Transaction": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> bool: ...
+
 class Connection:
     """Single database connection."""
     
     def __init__(
         self, 
         config: ConnectionConfig, 
-        accept_invalid_certs: bool = False
+        accept_invalid_certs: bool = False,
+        accept_invalid_hostnames: bool = False
     ) -> None: ...
     
     def query(
         self, 
         sql: str, 
-        params: Optional[List[Any]] = None
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        row_factory: str = "dict",
+        model: Optional[Callable[..., Any]] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True,
+        max_rows: Optional[int] = None,
+        with_columns: bool = False
+    ) -> Union[List[Any], Tuple[List[str], List[Any]]]:
+        """Execute query and return rows as dicts, or with row_factory="tuple"/"namedtuple".
+
+        With model=, each row is returned as model(**row_dict). With paramstyle=
+        "format"/"pyformat"/"named", %s, %(name)s or :name placeholders are rewritten to $n.
+        With prepared=False the SQL is sent over the simple query protocol (for pgbouncer in
+        transaction mode): no params are allowed and values come back as strings.
+        With max_rows=N, raises RuntimeError instead of returning more than N rows.
+        With with_columns=True, returns (columns, rows), columns being the names in order.
+        """
+        ...
+    
+    def query_columns(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Dict[str, List[Any]]:
+        """Execute query and return {column: [values...]} in column order."""
+        ...
+    
+    def fetch_column(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        column: Union[int, str] = 0,
+        timeout_secs: Optional[int] = None
+    ) -> List[Any]:
+        """Execute query and return one column's values (by index or name) as a flat list."""
+        ...
+    
+    def row_count(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> int:
+        """Rows the query returns, counted on the server with SELECT count(*) FROM (sql) sub.
+
+        Nothing is fetched, but the query still runs in full.
+        """
+        ...
+    
+    def explain(
+        self,
+        sql: str,
+        params: Optional[List[Any]] = None,
+        analyze: bool = False,
+        format: str = "json",
+        buffers: bool = False,
+        verbose: bool = False,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """The query's plan from EXPLAIN: the parsed JSON document (format="json") or a list of lines (format="text").
+
+        With analyze=True the query really runs, including any changes it makes.
+        """
+        ...
+    
+    def query_df(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pandas DataFrame (requires pandas)."""
+        ...
+    
+    def query_arrow(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pyarrow.RecordBatch (requires pyarrow)."""
+        ...
+    
+    def query_cancellable(
+        self, 
+        sql: str, 
+        token: "CancelToken",
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
     ) -> List[Dict[str, Any]]:
-        """Execute query and return rows as list of dicts."""
+        """Execute query that another thread can abort with token.cancel()."""
         ...
     
     def execute(
         self, 
         sql: str, 
-        params: Optional[List[Any]] = None
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
     ) -> int:
         """Execute statement and return affected row count."""
         ...
     
+    def execute_with_tag(
+        self, 
+        sql: str, 
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
+    ) -> str:
+        """Execute statement and return its command tag, e.g. "INSERT 0 5" or "CREATE TABLE".
+
+        The tag is rebuilt from the statement's leading keywords and row count, not read from the server.
+        """
+        ...
+    
+    def upsert(
+        self,
+        table: str,
+        columns: List[str],
+        values: List[Any],
+        conflict_columns: List[str],
+        update_columns: Optional[List[str]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> List[Dict[str, Any]]:
+        """INSERT ... ON CONFLICT (conflict_columns) DO UPDATE SET col = EXCLUDED.col, returning the row.
+
+        update_columns must be inserted columns and default to every one outside conflict_columns; with none to
+        update the conflict is left alone (DO NOTHING) and nothing is returned.
+        """
+        ...
+    
+    def describe(
+        self,
+        sql: str,
+        params: Optional[Sequence[Any]] = None,
+        with_params: bool = False
+    ) -> Union[List[Tuple[str, int, str]], Tuple[List[Tuple[str, int, str]], List[Tuple[int, str]]]]:
+        """Describe result columns as (name, type_oid, type_name) without executing.
+
+        With with_params=True, returns (columns, params): the (type_oid, type_name) of each $n parameter.
+        """
+        ...
+    
+    def columns_only(self, sql: str, params: Optional[Sequence[Any]] = None) -> List[str]:
+        """Result column names in order, without executing."""
+        ...
+    
+    def cursor(self) -> Cursor:
+        """Open a DB-API 2.0 cursor on this connection."""
+        ...
+    
+    def transaction(self, read_only: bool = False, isolation_level: Optional[str] = None) -> Transaction:
+        """Transaction for a with block; read_only=True begins it READ ONLY.
+        isolation_level is "read committed", "repeatable read" or "serializable"."""
+        ...
+    
+    def advisory_lock(self, key: Union[int, Tuple[int, int]]) -> AdvisoryLock:
+        """Wait for a session-level advisory lock (a bigint key or a pair of int32s)."""
+        ...
+    
+    def try_advisory_lock(self, key: Union[int, Tuple[int, int]]) -> bool:
+        """Take the advisory lock if it's free; returns whether it was taken."""
+        ...
+    
+    def advisory_unlock(self, key: Union[int, Tuple[int, int]]) -> bool:
+        """Release one hold of the advisory lock; False if this session didn't hold it."""
+        ...
+    
+    def backend_pid(self) -> int:
+        """Process ID of the server backend handling this connection."""
+        ...
+    
+    def server_version(self) -> int:
+        """Numeric server version, e.g. 140005 (cached after the first call)."""
+        ...
+    
+    def server_version_info(self) -> Tuple[int, int]:
+        """Server version as (major, minor), e.g. (14, 5)."""
+        ...
+    
+    def parameter_status(self, name: str) -> Optional[str]:
+        """A parameter the server reports, e.g. "server_encoding" or "TimeZone"; None if not reported.
+
+        Read from the values cached as the server reports them, without a round trip.
+        """
+        ...
+    
+    def ping(self, timeout_secs: int = 5) -> float:
+        """Round-trip latency of a trivial query in milliseconds; raises on failure."""
+        ...
+    
+    def with_database(self, name: str) -> "Connection":
+        """Reconnect to database `name` with the same settings, closing this connection.
+
+        Not a `USE`: session state doesn't carry over.
+        """
+        ...
+    
     def is_closed(self) -> bool:
         """Check if connection is closed."""
         ...
@@ -96,39 +488,378 @@ class Connection:
     def __enter__(self) -> "Connection": ...
     def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
+class PooledConnection:
+    """A pool connection checked out for exclusive use."""
+    
+    def query(
+        self, 
+        sql: str, 
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        row_factory: str = "dict",
+        model: Optional[Callable[..., Any]] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True,
+        max_rows: Optional[int] = None,
+        with_columns: bool = False
+    ) -> Union[List[Any], Tuple[List[str], List[Any]]]:
+        """Execute query and return rows as dicts, or with row_factory="tuple"/"namedtuple".
+
+        With model=, each row is returned as model(**row_dict). With paramstyle=
+        "format"/"pyformat"/"named", %s, %(name)s or :name placeholders are rewritten to $n.
+        With prepared=False the SQL is sent over the simple query protocol (for pgbouncer in
+        transaction mode): no params are allowed and values come back as strings.
+        With max_rows=N, raises RuntimeError instead of returning more than N rows.
+        With with_columns=True, returns (columns, rows), columns being the names in order.
+        """
+        ...
+    
+    def query_columns(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Dict[str, List[Any]]:
+        """Execute query and return {column: [values...]} in column order."""
+        ...
+    
+    def fetch_column(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        column: Union[int, str] = 0,
+        timeout_secs: Optional[int] = None
+    ) -> List[Any]:
+        """Execute query and return one column's values (by index or name) as a flat list."""
+        ...
+    
+    def row_count(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> int:
+        """Rows the query returns, counted on the server with SELECT count(*) FROM (sql) sub.
+
+        Nothing is fetched, but the query still runs in full.
+        """
+        ...
+    
+    def explain(
+        self,
+        sql: str,
+        params: Optional[List[Any]] = None,
+        analyze: bool = False,
+        format: str = "json",
+        buffers: bool = False,
+        verbose: bool = False,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """The query's plan from EXPLAIN: the parsed JSON document (format="json") or a list of lines (format="text").
+
+        With analyze=True the query really runs, including any changes it makes.
+        """
+        ...
+    
+    def query_df(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pandas DataFrame (requires pandas)."""
+        ...
+    
+    def query_arrow(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pyarrow.RecordBatch (requires pyarrow)."""
+        ...
+    
+    def fetch_one(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Optional[Dict[str, Any]]:
+        """Fetch single row or None."""
+        ...
+    
+    def execute(
+        self, 
+        sql: str, 
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
+    ) -> int:
+        """Execute statement and return affected row count."""
+        ...
+    
+    def execute_with_tag(
+        self, 
+        sql: str, 
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
+    ) -> str:
+        """Execute statement and return its command tag, e.g. "INSERT 0 5" or "CREATE TABLE".
+
+        The tag is rebuilt from the statement's leading keywords and row count, not read from the server.
+        """
+        ...
+    
+    def upsert(
+        self,
+        table: str,
+        columns: List[str],
+        values: List[Any],
+        conflict_columns: List[str],
+        update_columns: Optional[List[str]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> List[Dict[str, Any]]:
+        """INSERT ... ON CONFLICT (conflict_columns) DO UPDATE SET col = EXCLUDED.col, returning the row.
+
+        update_columns must be inserted columns and default to every one outside conflict_columns; with none to
+        update the conflict is left alone (DO NOTHING) and nothing is returned.
+        """
+        ...
+    
+    def execute_many(
+        self, 
+        statements: List[Tuple[str, Optional[List[Any]]]]
+    ) -> List[int]:
+        """Execute multiple statements in a transaction on this connection."""
+        ...
+    
+    def cursor(self) -> Cursor:
+        """Open a DB-API 2.0 cursor on this connection."""
+        ...
+    
+    def transaction(self, read_only: bool = False, isolation_level: Optional[str] = None) -> Transaction:
+        """Transaction for a with block; read_only=True begins it READ ONLY.
+        isolation_level is "read committed", "repeatable read" or "serializable"."""
+        ...
+    
+    def advisory_lock(self, key: Union[int, Tuple[int, int]]) -> AdvisoryLock:
+        """Wait for a session-level advisory lock (a bigint key or a pair of int32s)."""
+        ...
+    
+    def try_advisory_lock(self, key: Union[int, Tuple[int, int]]) -> bool:
+        """Take the advisory lock if it's free; returns whether it was taken."""
+        ...
+    
+    def advisory_unlock(self, key: Union[int, Tuple[int, int]]) -> bool:
+        """Release one hold of the advisory lock; False if this session didn't hold it."""
+        ...
+    
+    def backend_pid(self) -> int:
+        """Process ID of the server backend handling this connection."""
+        ...
+    
+    def is_released(self) -> bool:
+        """Check if the connection was returned to the pool."""
+        ...
+    
+    def release(self) -> None:
+        """Return the connection to the pool."""
+        ...
+    
+    def __enter__(self) -> "PooledConnection": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
 class AsyncPool:
     """Connection pool for production use."""
     
     def __init__(
         self, 
         config: ConnectionConfig, 
-        accept_invalid_certs: bool = False
+        accept_invalid_certs: bool = False,
+        accept_invalid_hostnames: bool = False
     ) -> None: ...
     
     def query(
         self, 
         sql: str, 
-        params: Optional[List[Any]] = None
-    ) -> List[Dict[str, Any]]:
-        """Execute query and return rows as list of dicts."""
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        row_factory: str = "dict",
+        model: Optional[Callable[..., Any]] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True,
+        max_rows: Optional[int] = None,
+        with_columns: bool = False
+    ) -> Union[List[Any], Tuple[List[str], List[Any]]]:
+        """Execute query and return rows as dicts, or with row_factory="tuple"/"namedtuple".
+
+        With model=, each row is returned as model(**row_dict). With paramstyle=
+        "format"/"pyformat"/"named", %s, %(name)s or :name placeholders are rewritten to $n.
+        With prepared=False the SQL is sent over the simple query protocol (for pgbouncer in
+        transaction mode): no params are allowed and values come back as strings.
+        With max_rows=N, raises RuntimeError instead of returning more than N rows.
+        With with_columns=True, returns (columns, rows), columns being the names in order.
+        """
+        ...
+    
+    def query_columns(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Dict[str, List[Any]]:
+        """Execute query and return {column: [values...]} in column order."""
+        ...
+    
+    def fetch_column(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        column: Union[int, str] = 0,
+        timeout_secs: Optional[int] = None
+    ) -> List[Any]:
+        """Execute query and return one column's values (by index or name) as a flat list."""
+        ...
+    
+    def row_count(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> int:
+        """Rows the query returns, counted on the server with SELECT count(*) FROM (sql) sub.
+
+        Nothing is fetched, but the query still runs in full.
+        """
+        ...
+    
+    def explain(
+        self,
+        sql: str,
+        params: Optional[List[Any]] = None,
+        analyze: bool = False,
+        format: str = "json",
+        buffers: bool = False,
+        verbose: bool = False,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """The query's plan from EXPLAIN: the parsed JSON document (format="json") or a list of lines (format="text").
+
+        With analyze=True the query really runs, including any changes it makes.
+        """
+        ...
+    
+    def query_df(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pandas DataFrame (requires pandas)."""
+        ...
+    
+    def query_arrow(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> Any:
+        """Execute query and return a pyarrow.RecordBatch (requires pyarrow)."""
+        ...
+    
+    def iter_rows(
+        self, 
+        sql: str, 
+        params: Optional[List[Any]] = None,
+        fetch_size: Optional[int] = None,
+        prefetch: Optional[int] = None
+    ) -> RowIterator:
+        """Iterate over rows as dicts, fetching fetch_size rows per round trip.
+
+        fetch_size defaults to the config's default_fetch_size (1000); prefetch is its older name.
+        The connection is returned to the pool when the rows run out, on close(),
+        or when the iterator is garbage-collected.
+        """
+        ...
+    
+    def time_buckets(
+        self,
+        start: datetime,
+        end: datetime,
+        interval: Union[timedelta, str],
+        fetch_size: Optional[int] = None
+    ) -> RowIterator:
+        """Stream {"bucket": datetime} rows from start to end inclusive, interval apart, like iter_rows.
+
+        interval is a timedelta or an interval string such as "15 minutes".
+        start and end must both be naive (TIMESTAMP) or both tz-aware (TIMESTAMPTZ).
+        """
         ...
     
     def fetch_one(
         self, 
         sql: str, 
-        params: Optional[List[Any]] = None
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
     ) -> Optional[Dict[str, Any]]:
         """Fetch single row or None."""
         ...
     
+    def query_cancellable(
+        self, 
+        sql: str, 
+        token: "CancelToken",
+        params: Optional[List[Any]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> List[Dict[str, Any]]:
+        """Execute query that another thread can abort with token.cancel()."""
+        ...
+    
+    def pipeline(
+        self, 
+        queries: List[Tuple[str, Optional[List[Any]]]],
+        timeout_secs: Optional[int] = None
+    ) -> List[List[Dict[str, Any]]]:
+        """Run independent queries concurrently on one connection; results in input order.
+
+        timeout_secs bounds the whole pipeline; an error's sql is that of the query that failed.
+        """
+        ...
+    
     def execute(
         self, 
         sql: str, 
-        params: Optional[List[Any]] = None
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
     ) -> int:
         """Execute statement and return affected row count."""
         ...
     
+    def execute_with_tag(
+        self, 
+        sql: str, 
+        params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None,
+        timeout_secs: Optional[int] = None,
+        paramstyle: Optional[str] = None,
+        prepared: bool = True
+    ) -> str:
+        """Execute statement and return its command tag, e.g. "INSERT 0 5" or "CREATE TABLE".
+
+        The tag is rebuilt from the statement's leading keywords and row count, not read from the server.
+        """
+        ...
+    
+    def upsert(
+        self,
+        table: str,
+        columns: List[str],
+        values: List[Any],
+        conflict_columns: List[str],
+        update_columns: Optional[List[str]] = None,
+        timeout_secs: Optional[int] = None
+    ) -> List[Dict[str, Any]]:
+        """INSERT ... ON CONFLICT (conflict_columns) DO UPDATE SET col = EXCLUDED.col, returning the row.
+
+        update_columns must be inserted columns and default to every one outside conflict_columns; with none to
+        update the conflict is left alone (DO NOTHING) and nothing is returned.
+        """
+        ...
+    
     def execute_many(
         self, 
         statements: List[Tuple[str, Optional[List[Any]]]]
@@ -136,50 +867,279 @@ class AsyncPool:
         """Execute multiple statements in a transaction."""
         ...
     
+    def bulk_upsert(
+        self,
+        table: str,
+        columns: List[str],
+        rows: List[List[Any]],
+        conflict_columns: List[str],
+        update_columns: Optional[List[str]] = None
+    ) -> int:
+        """Insert or update many rows in one transaction; returns the rows inserted or updated.
+
+        Rows go out as multi-row INSERT ... VALUES (...), (...) ON CONFLICT statements, as
+        many rows per statement as the 65535 bind parameter limit allows. A conflict key may
+        appear only once among those rows.
+        """
+        ...
+    
     def execute_batch(
         self, 
         sql: str, 
-        params_list: List[List[Any]]
+        params_list: List[List[Any]],
+        chunk_size: Optional[int] = None,
+        progress_callback: Optional[Callable[[int, int], Any]] = None
     ) -> int:
-        """High-performance bulk insert with prepared statement reuse."""
+        """High-performance bulk insert with prepared statement reuse.
+
+        With chunk_size, rows are pipelined N at a time and each chunk is committed
+        in its own transaction. progress_callback(done, total) runs after each chunk.
+        """
         ...
     
     def execute_raw(self, sql: str) -> None:
         """Execute raw SQL batch (multiple statements separated by semicolons)."""
         ...
     
-    def is_healthy(self) -> bool:
+    def set_slow_query_callback(self, callback: Optional[Callable[[str, float], Any]]) -> None:
+        """Call callback(sql, duration_ms) for queries slower than slow_query_threshold_ms."""
+        ...
+    
+    def acquire(self) -> PooledConnection:
+        """Check out one connection until released (use as context manager)."""
+        ...
+    
+    def transaction(
+        self,
+        body: Callable[[PooledConnection], T],
+        retry: int = 0,
+        read_only: bool = False,
+        isolation_level: Optional[str] = None,
+        retry_backoff_ms: int = 50,
+    ) -> T:
+        """Run body(conn) in a transaction on an acquired connection and commit.
+
+        With retry=N, serialization failures and deadlocks (SQLSTATE 40001/40P01)
+        roll back and re-run body up to N more times, waiting retry_backoff_ms
+        (doubled each time) in between. body must be safe to re-run.
+        """
+        ...
+    
+    def describe(
+        self,
+        sql: str,
+        params: Optional[Sequence[Any]] = None,
+        with_params: bool = False
+    ) -> Union[List[Tuple[str, int, str]], Tuple[List[Tuple[str, int, str]], List[Tuple[int, str]]]]:
+        """Describe result columns as (name, type_oid, type_name) without executing.
+
+        With with_params=True, returns (columns, params): the (type_oid, type_name) of each $n parameter.
+        """
+        ...
+    
+    def columns_only(self, sql: str, params: Optional[Sequence[Any]] = None) -> List[str]:
+        """Result column names in order, without executing."""
+        ...
+    
+    def server_version(self) -> int:
+        """Numeric server version, e.g. 140005 (cached after the first call)."""
+        ...
+    
+    def server_version_info(self) -> Tuple[int, int]:
+        """Server version as (major, minor), e.g. (14, 5)."""
+        ...
+    
+    def is_healthy(self, timeout_secs: int = 5) -> bool:
         """Check if pool connections are healthy."""
         ...
     
+    def ping(self, timeout_secs: int = 5) -> float:
+        """Round-trip latency of a trivial query in milliseconds; raises on failure."""
+        ...
+    
     def pool_status(self) -> Dict[str, int]:
-        """Get pool statistics: size, available, waiting."""
+        """Get pool statistics: size, available, waiting, max_size,
+        connections_created, connections_recycled."""
+        ...
+    
+    def resize(self, new_size: int) -> int:
+        """Change max_size at runtime, closing surplus connections as they're
+        returned. Returns the new max_size."""
+        ...
+    
+    def metrics(self) -> Dict[str, Union[int, float]]:
+        """Cumulative statement metrics: queries, errors, timeouts,
+        total_time_ms, max_time_ms."""
         ...
     
     def close(self) -> None:
         """Close all connections in the pool."""
         ...
     
+    def drain(self, timeout_secs: int = 30) -> bool:
+        """Stop handing out connections and wait for checked-out ones to be
+        returned, then close. False if some were still in use at the deadline."""
+        ...
+    
     def __enter__(self) -> "AsyncPool": ...
     def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
 
+class FailoverPool:
+    """Primary/standby pool pair with automatic failover."""
+    
+    primary: AsyncPool
+    standby: AsyncPool
+    failover_count: int
+    
+    def __init__(
+        self, 
+        primary: AsyncPool, 
+        standby: AsyncPool, 
+        failure_threshold: int = 3, 
+        read_from_standby: bool = False
+    ) -> None: ...
+    
+    def query(self, sql: str, params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None, timeout_secs: Optional[int] = None, row_factory: str = "dict", model: Optional[Callable[..., Any]] = None, paramstyle: Optional[str] = None, prepared: bool = True, max_rows: Optional[int] = None, with_columns: bool = False) -> Union[List[Any], Tuple[List[str], List[Any]]]:
+        """Execute query on the primary (or standby if read_from_standby)."""
+        ...
+    
+    def query_columns(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> Dict[str, List[Any]]:
+        """Execute query returning {column: [values...]} on the primary (or standby if read_from_standby)."""
+        ...
+    
+    def fetch_column(self, sql: str, params: Optional[List[Any]] = None, column: Union[int, str] = 0, timeout_secs: Optional[int] = None) -> List[Any]:
+        """One column's values as a flat list (standby if read_from_standby)."""
+        ...
+    
+    def row_count(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> int:
+        """Rows the query returns, counted on the server (standby if read_from_standby)."""
+        ...
+    
+    def explain(self, sql: str, params: Optional[List[Any]] = None, analyze: bool = False, format: str = "json", buffers: bool = False, verbose: bool = False, timeout_secs: Optional[int] = None) -> Any:
+        """The query's plan from EXPLAIN (standby if read_from_standby)."""
+        ...
+    
+    def query_df(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> Any:
+        """Execute query returning a pandas DataFrame on the primary (or standby if read_from_standby)."""
+        ...
+    
+    def query_arrow(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> Any:
+        """Execute query returning a pyarrow.RecordBatch on the primary (or standby if read_from_standby)."""
+        ...
+    
+    def iter_rows(self, sql: str, params: Optional[List[Any]] = None, fetch_size: Optional[int] = None, prefetch: Optional[int] = None) -> RowIterator:
+        """Stream rows through a server-side cursor (standby if read_from_standby)."""
+        ...
+    
+    def fetch_one(self, sql: str, params: Optional[List[Any]] = None, timeout_secs: Optional[int] = None) -> Optional[Dict[str, Any]]:
+        """Fetch single row from the primary (or standby if read_from_standby)."""
+        ...
+    
+    def execute(self, sql: str, params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None, timeout_secs: Optional[int] = None, paramstyle: Optional[str] = None, prepared: bool = True) -> int:
+        """Execute statement on the primary."""
+        ...
+    
+    def execute_with_tag(self, sql: str, params: Optional[Union[Sequence[Any], Mapping[str, Any]]] = None, timeout_secs: Optional[int] = None, paramstyle: Optional[str] = None, prepared: bool = True) -> str:
+        """Execute statement on the primary and return its command tag."""
+        ...
+    
+    def upsert(self, table: str, columns: List[str], values: List[Any], conflict_columns: List[str], update_columns: Optional[List[str]] = None, timeout_secs: Optional[int] = None) -> List[Dict[str, Any]]:
+        """Insert or update a row on the primary, returning it."""
+        ...
+    
+    def execute_many(self, statements: List[Tuple[str, Optional[List[Any]]]]) -> List[int]:
+        """Execute multiple statements in a transaction on the primary."""
+        ...
+    
+    def bulk_upsert(self, table: str, columns: List[str], rows: List[List[Any]], conflict_columns: List[str], update_columns: Optional[List[str]] = None) -> int:
+        """Insert or update many rows in one transaction on the primary."""
+        ...
+    
+    def execute_batch(
+        self,
+        sql: str,
+        params_list: List[List[Any]],
+        chunk_size: Optional[int] = None,
+        progress_callback: Optional[Callable[[int, int], Any]] = None
+    ) -> int:
+        """Bulk insert on the primary."""
+        ...
+    
+    def check_health(self) -> bool:
+        """Probe the primary; returns True if the standby was promoted."""
+        ...
+    
+    def failover(self) -> bool:
+        """Promote the standby now if it is healthy."""
+        ...
+    
+    def close(self) -> None:
+        """Close both pools."""
+        ...
+    
+    def __enter__(self) -> "FailoverPool": ...
+    def __exit__(self, exc_type: Any, exc_val: Any, exc_tb: Any) -> None: ...
+
 def create_pool(
     config: ConnectionConfig, 
-    accept_invalid_certs: bool = False
+    accept_invalid_certs: bool = False,
+    accept_invalid_hostnames: bool = False
 ) -> AsyncPool:
     """Create a connection pool."""
     ...
 
 def connect(
     config: ConnectionConfig, 
-    accept_invalid_certs: bool = False
+    accept_invalid_certs: bool = False,
+    accept_invalid_hostnames: bool = False
 ) -> Connection:
     """Create a single connection."""
     ...
 
 def connect_url(
     url: str, 
-    accept_invalid_certs: bool = False
+    accept_invalid_certs: bool = False,
+    accept_invalid_hostnames: bool = False
 ) -> Connection:
     """Create connection from URL string."""
     ...
+
+def expand_in(sql: str, param_index: int, params: Sequence[Any]) -> Tuple[str, List[Any]]:
+    """Expand $param_index (a list parameter) into one placeholder per item, renumbering later ones.
+
+    expand_in("... IN ($1)", 1, [[1, 2, 3]]) -> ("... IN ($1, $2, $3)", [1, 2, 3])
+    """
+    ...
+
+def register_adapter(py_type: type, adapter: Optional[Callable[[Any], Any]]) -> None:
+    """Bind instances of py_type (and subclasses) as adapter(value), e.g. a str or Decimal; None unregisters."""
+    ...
+
+def register_converter(type_oid: int, converter: Optional[Callable[[Any], Any]]) -> None:
+    """Return non-NULL columns of type_oid as converter(decoded_value); None unregisters."""
+    ...
+
+def register_enum(type_name: str, enum_class: Optional[type]) -> None:
+    """Return columns of the Postgres enum type_name as enum_class(label) members; None unregisters."""
+    ...
+
+def init_tracing(
+    callback: Optional[Callable[[str, Dict[str, Any], int, int], Any]],
+    max_sql_length: int = 1024,
+    include_sql: bool = True
+) -> None:
+    """Call callback(name, attributes, start_ns, end_ns) for each query/execute/fetch_one span.
+
+    attributes has db.system, db.operation, db.statement (cut to max_sql_length
+    characters; left out with include_sql=False), db.params, db.rows and, on
+    failure, error. None stops tracing.
+    """
+    ...
+
+def quote_identifier(name: str) -> str:
+    """Double-quote an identifier, doubling embedded quotes: 'my "col"' -> '"my ""col"""'."""
+    ...
+
+def quote_literal(value: Any) -> str:
+    """Quote a value as a SQL string literal; None -> NULL, bools -> TRUE/FALSE."""
+    ...
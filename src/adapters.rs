@@ -0,0 +1,156 @@
+//! User-registered conversions for parameters and result columns
+//!
+//! Adapters turn instances of a Python type into a value the driver already
+//! binds (str, int, Decimal, ...); converters post-process a column's decoded
+//! value by type OID; enum classes map a Postgres enum's labels to members.
+//! All registries are process-wide.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::types::PyType;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+static ADAPTERS: RwLock<Vec<(Py<PyType>, PyObject)>> = RwLock::new(Vec::new());
+static CONVERTERS: RwLock<BTreeMap<u32, PyObject>> = RwLock::new(BTreeMap::new());
+static ENUMS: RwLock<BTreeMap<String, Py<PyType>>> = RwLock::new(BTreeMap::new());
+
+/// Set once anything is registered, so unregistered use skips the locks
+static ANY_ADAPTER: AtomicBool = AtomicBool::new(false);
+static ANY_CONVERTER: AtomicBool = AtomicBool::new(false);
+
+fn check_callable(callable: &Bound<'_, PyAny>) -> PyResult<()> {
+    match callable.is_callable() {
+        true => Ok(()),
+        false => Err(PyTypeError::new_err(format!("{} is not callable", callable))),
+    }
+}
+
+/// Bind instances of `py_type` (and its subclasses) as `adapter(value)`
+///
+/// The adapter returns something the driver already binds, e.g. a str or a
+/// Decimal. Registering a type again replaces its adapter; None removes it.
+#[pyfunction]
+#[pyo3(signature = (py_type, adapter))]
+pub fn register_adapter(py_type: &Bound<'_, PyType>, adapter: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+    if let Some(adapter) = adapter {
+        check_callable(adapter)?;
+    }
+    let mut adapters = ADAPTERS.write().expect("adapter registry poisoned");
+    adapters.retain(|(ty, _)| !ty.bind(py_type.py()).is(py_type));
+    if let Some(adapter) = adapter {
+        adapters.push((py_type.clone().unbind(), adapter.clone().unbind()));
+        ANY_ADAPTER.store(true, Ordering::Release);
+    }
+    Ok(())
+}
+
+/// Return columns of type `type_oid` as `converter(value)`
+///
+/// The converter receives the value as it would otherwise be returned (a str
+/// for text, a Decimal for numeric, ...); it isn't called for NULLs.
+/// Registering an OID again replaces its converter; None removes it.
+#[pyfunction]
+#[pyo3(signature = (type_oid, converter))]
+pub fn register_converter(type_oid: u32, converter: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+    let mut converters = CONVERTERS.write().expect("converter registry poisoned");
+    match converter {
+        Some(converter) => {
+            check_callable(converter)?;
+            converters.insert(type_oid, converter.clone().unbind());
+            ANY_CONVERTER.store(true, Ordering::Release);
+        }
+        None => {
+            converters.remove(&type_oid);
+        }
+    }
+    Ok(())
+}
+
+/// Return columns of the Postgres enum `type_name` as members of `enum_class`
+///
+/// Each label is looked up as `enum_class(label)`, so member values must be the
+/// labels. Registering a type again replaces its class; None removes it.
+#[pyfunction]
+#[pyo3(signature = (type_name, enum_class))]
+pub fn register_enum(type_name: &str, enum_class: Option<&Bound<'_, PyType>>) -> PyResult<()> {
+    let mut enums = ENUMS.write().expect("enum registry poisoned");
+    match enum_class {
+        Some(enum_class) => {
+            let is_enum = enum_class.py().import_bound("enum")?.getattr("Enum")?;
+            if !enum_class.is_subclass(&is_enum)? {
+                return Err(PyTypeError::new_err(format!("{} is not an enum.Enum subclass", enum_class.name()?)));
+            }
+            enums.insert(type_name.to_string(), enum_class.clone().unbind());
+        }
+        None => {
+            enums.remove(type_name);
+        }
+    }
+    Ok(())
+}
+
+/// The member of the class registered for enum type `type_name` with value `label`,
+/// or `None` if no class is registered for the type
+pub fn enum_member(py: Python<'_>, type_name: &str, label: &str) -> PyResult<Option<PyObject>> {
+    let enum_class = ENUMS.read().expect("enum registry poisoned").get(type_name).map(|c| c.clone_ref(py));
+    let Some(enum_class) = enum_class else {
+        return Ok(None);
+    };
+    let enum_class = enum_class.bind(py);
+    match enum_class.call1((label,)) {
+        Ok(member) => Ok(Some(member.unbind())),
+        Err(err) if err.is_instance_of::<PyValueError>(py) => Err(PyValueError::new_err(format!(
+            "'{}' from enum type {} is not a value of {}",
+            label,
+            type_name,
+            enum_class.name()?
+        ))),
+        Err(err) => Err(err),
+    }
+}
+
+/// The adapted form of `ob` if an adapter is registered for its type
+pub fn adapt<'py>(ob: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    if !ANY_ADAPTER.load(Ordering::Acquire) {
+        return Ok(None);
+    }
+    // Copied out so isinstance() checks, which can run Python code, happen without the lock
+    let adapters: Vec<_> = ADAPTERS.read().expect("adapter registry poisoned")
+        .iter()
+        .map(|(ty, adapter)| (ty.clone_ref(ob.py()), adapter.clone_ref(ob.py())))
+        .collect();
+    let mut found = None;
+    for (ty, adapter) in adapters {
+        if ob.is_instance(ty.bind(ob.py()))? {
+            found = Some((ty, adapter));
+            break;
+        }
+    }
+    let Some((ty, adapter)) = found else {
+        return Ok(None);
+    };
+
+    let adapted = adapter.bind(ob.py()).call1((ob,))?;
+    if adapted.is_instance(ty.bind(ob.py()))? {
+        return Err(PyTypeError::new_err(format!(
+            "The adapter for {} returned another {}; it must return a bindable value such as str",
+            ty.bind(ob.py()).name()?,
+            ty.bind(ob.py()).name()?
+        )));
+    }
+    Ok(Some(adapted))
+}
+
+/// Apply the converter registered for `oid`, if any, to a decoded non-NULL value
+pub fn convert(py: Python<'_>, oid: u32, value: PyObject) -> PyResult<PyObject> {
+    if !ANY_CONVERTER.load(Ordering::Acquire) || value.is_none(py) {
+        return Ok(value);
+    }
+    let converter = CONVERTERS.read().expect("converter registry poisoned").get(&oid).map(|c| c.clone_ref(py));
+    match converter {
+        Some(converter) => Ok(converter.bind(py).call1((value,))?.unbind()),
+        None => Ok(value),
+    }
+}
@@ -0,0 +1,130 @@
+//! Exporting result rows to Apache Arrow
+
+use std::sync::Arc;
+use arrow_array::ffi::to_ffi;
+use arrow_array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, RecordBatch, RecordBatchOptions, StringArray, StructArray, Time64MicrosecondArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{Field, Schema};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyImportError, PyValueError};
+use rust_decimal::Decimal;
+use tokio_postgres::types::{FromSql, Type};
+use tokio_postgres::{Column, Row};
+use uuid::Uuid;
+
+/// Read column `idx` of every row, mapping NULLs to `None`
+fn values<'a, T: FromSql<'a>>(rows: &'a [Row], idx: usize, column: &Column) -> PyResult<Vec<Option<T>>> {
+    rows.iter()
+        .map(|row| row.try_get::<_, Option<T>>(idx))
+        .collect::<Result<_, _>>()
+        .map_err(|e| PyValueError::new_err(format!("Cannot read column '{}': {}", column.name(), e)))
+}
+
+/// NUMERIC as Decimal128, using the widest scale present in the column
+fn numeric_array(values: Vec<Option<Decimal>>, column: &Column) -> PyResult<ArrayRef> {
+    let scale = values.iter().flatten().map(|d| d.scale()).max().unwrap_or(0);
+    let too_wide = || PyValueError::new_err(format!("NUMERIC column '{}' does not fit Decimal128", column.name()));
+    let scaled = values.into_iter()
+        .map(|value| value.map(|d| {
+            10i128.checked_pow(scale - d.scale())
+                .and_then(|factor| d.mantissa().checked_mul(factor))
+                .ok_or_else(too_wide)
+        }).transpose())
+        .collect::<PyResult<Vec<_>>>()?;
+    let array = Decimal128Array::from(scaled)
+        .with_precision_and_scale(38, scale as i8)
+        .map_err(|_| too_wide())?;
+    Ok(Arc::new(array))
+}
+
+/// Build the Arrow array for one result column
+fn column_to_array(rows: &[Row], idx: usize, column: &Column) -> PyResult<ArrayRef> {
+    let array: ArrayRef = match *column.type_() {
+        Type::BOOL => Arc::new(BooleanArray::from(values::<bool>(rows, idx, column)?)),
+        Type::INT2 => Arc::new(Int16Array::from(values::<i16>(rows, idx, column)?)),
+        Type::INT4 => Arc::new(Int32Array::from(values::<i32>(rows, idx, column)?)),
+        Type::INT8 => Arc::new(Int64Array::from(values::<i64>(rows, idx, column)?)),
+        Type::FLOAT4 => Arc::new(Float32Array::from(values::<f32>(rows, idx, column)?)),
+        Type::FLOAT8 => Arc::new(Float64Array::from(values::<f64>(rows, idx, column)?)),
+        Type::NUMERIC => numeric_array(values::<Decimal>(rows, idx, column)?, column)?,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            Arc::new(StringArray::from(values::<&str>(rows, idx, column)?))
+        }
+        Type::BYTEA => Arc::new(BinaryArray::from(values::<&[u8]>(rows, idx, column)?)),
+        Type::UUID => {
+            let uuids = values::<Uuid>(rows, idx, column)?;
+            Arc::new(StringArray::from_iter(uuids.into_iter().map(|u| u.map(|u| u.to_string()))))
+        }
+        Type::JSON | Type::JSONB => {
+            let docs = values::<serde_json::Value>(rows, idx, column)?;
+            Arc::new(StringArray::from_iter(docs.into_iter().map(|d| d.map(|d| d.to_string()))))
+        }
+        Type::DATE => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).expect("valid date");
+            let dates = values::<NaiveDate>(rows, idx, column)?;
+            Arc::new(Date32Array::from_iter(dates.into_iter().map(|d| d.map(|d| (d - epoch).num_days() as i32))))
+        }
+        Type::TIME => {
+            let times = values::<NaiveTime>(rows, idx, column)?;
+            Arc::new(Time64MicrosecondArray::from_iter(
+                times.into_iter().map(|t| t.map(|t| (t - NaiveTime::MIN).num_microseconds().unwrap_or(0))),
+            ))
+        }
+        Type::TIMESTAMP => {
+            let timestamps = values::<NaiveDateTime>(rows, idx, column)?;
+            Arc::new(TimestampMicrosecondArray::from_iter(
+                timestamps.into_iter().map(|t| t.map(|t| t.and_utc().timestamp_micros())),
+            ))
+        }
+        Type::TIMESTAMPTZ => {
+            let timestamps = values::<DateTime<Utc>>(rows, idx, column)?;
+            Arc::new(
+                TimestampMicrosecondArray::from_iter(timestamps.into_iter().map(|t| t.map(|t| t.timestamp_micros())))
+                    .with_timezone("UTC"),
+            )
+        }
+        ref other => {
+            return Err(PyValueError::new_err(format!(
+                "Cannot export column '{}' of type {} to Arrow",
+                column.name(),
+                other
+            )))
+        }
+    };
+    Ok(array)
+}
+
+/// Build a RecordBatch holding every row, one Arrow array per column
+fn rows_to_record_batch(columns: &[Column], rows: &[Row]) -> PyResult<RecordBatch> {
+    let arrays = columns.iter().enumerate()
+        .map(|(idx, column)| column_to_array(rows, idx, column))
+        .collect::<PyResult<Vec<_>>>()?;
+    let fields: Vec<Field> = columns.iter().zip(&arrays)
+        .map(|(column, array)| Field::new(column.name(), array.data_type().clone(), true))
+        .collect();
+
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)
+        .map_err(|e| PyValueError::new_err(format!("Cannot build Arrow batch: {}", e)))
+}
+
+/// Convert result rows to a `pyarrow.RecordBatch` through the Arrow C Data Interface
+///
+/// The column buffers are handed to pyarrow as-is; no Python object is created per value.
+pub fn rows_to_pyarrow<'py>(py: Python<'py>, columns: &[Column], rows: &[Row]) -> PyResult<Bound<'py, PyAny>> {
+    let pyarrow = py.import_bound("pyarrow")
+        .map_err(|_| PyImportError::new_err("query_arrow requires pyarrow (pip install pyarrow)"))?;
+    let batch = rows_to_record_batch(columns, rows)?;
+
+    let data = StructArray::from(batch).into_data();
+    let (array, schema) = to_ffi(&data).map_err(|e| PyValueError::new_err(format!("Cannot export Arrow batch: {}", e)))?;
+    // pyarrow takes ownership by moving out of the structs; dropping them afterwards is a no-op
+    pyarrow.getattr("RecordBatch")?.call_method1(
+        "_import_from_c",
+        (&array as *const _ as usize, &schema as *const _ as usize),
+    )
+}
@@ -0,0 +1,99 @@
+//! Prepared statement caching
+
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Weak};
+use ahash::AHashMap;
+use lru::LruCache;
+use parking_lot::Mutex;
+use tokio_postgres::types::Type;
+use tokio_postgres::{Error, GenericClient, Statement};
+
+/// LRU cache of prepared statements for a single physical connection, keyed by SQL text
+pub struct StatementCache {
+    statements: Option<Mutex<LruCache<String, Statement>>>,
+}
+
+impl StatementCache {
+    /// Create a cache holding up to `capacity` statements (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        StatementCache {
+            statements: NonZeroUsize::new(capacity).map(|c| Mutex::new(LruCache::new(c))),
+        }
+    }
+
+    /// Prepare `sql`, reusing a previously prepared statement when cached
+    ///
+    /// `types` declares parameter types up front (see `types::param_types`); when
+    /// empty the server infers them all.
+    pub async fn prepare<C: GenericClient>(&self, client: &C, sql: &str, types: &[Type]) -> Result<Statement, Error> {
+        let Some(statements) = &self.statements else {
+            return client.prepare_typed(sql, types).await;
+        };
+
+        // Declared types change the statement, so they are part of the key
+        let key = if types.is_empty() {
+            Cow::Borrowed(sql)
+        } else {
+            let oids: Vec<_> = types.iter().map(Type::oid).collect();
+            Cow::Owned(format!("{}\0{:?}", sql, oids))
+        };
+
+        let cached = statements.lock().get(key.as_ref()).cloned();
+        if let Some(statement) = cached {
+            return Ok(statement);
+        }
+
+        let statement = client.prepare_typed(sql, types).await?;
+        statements.lock().put(key.into_owned(), statement.clone());
+        Ok(statement)
+    }
+
+    /// Drop all cached statements
+    pub fn clear(&self) {
+        if let Some(statements) = &self.statements {
+            statements.lock().clear();
+        }
+    }
+}
+
+/// A pooled connection's statement cache, tied to the lifetime of the connection
+type ConnectionEntry = (Weak<deadpool_postgres::StatementCache>, Arc<StatementCache>);
+
+/// Per-connection statement caches for a pool
+///
+/// Connections are identified by deadpool's own per-client statement cache `Arc`,
+/// which lives exactly as long as the physical connection. Holding a `Weak` to it
+/// keeps the address from being reused and lets us prune caches of dead connections.
+pub struct PoolStatementCache {
+    capacity: usize,
+    caches: Mutex<AHashMap<usize, ConnectionEntry>>,
+}
+
+impl PoolStatementCache {
+    pub fn new(capacity: usize) -> Self {
+        PoolStatementCache {
+            capacity,
+            caches: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Get the statement cache belonging to a pooled connection
+    pub fn for_connection(&self, client: &deadpool_postgres::Object) -> Arc<StatementCache> {
+        if self.capacity == 0 {
+            return Arc::new(StatementCache::new(0));
+        }
+
+        let key = Arc::as_ptr(&client.statement_cache) as usize;
+        let mut caches = self.caches.lock();
+        if let Some((_, cache)) = caches.get(&key) {
+            return cache.clone();
+        }
+
+        // New physical connection: forget caches of connections that have since closed
+        caches.retain(|_, (conn, _)| conn.strong_count() > 0);
+        let cache = Arc::new(StatementCache::new(self.capacity));
+        caches.insert(key, (Arc::downgrade(&client.statement_cache), cache.clone()));
+        cache
+    }
+}
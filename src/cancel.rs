@@ -0,0 +1,98 @@
+//! Cancelling running queries from another thread
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use crate::ClientTls;
+
+/// The backend a token currently points at
+struct Target {
+    token: tokio_postgres::CancelToken,
+    tls: ClientTls,
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+#[derive(Default)]
+struct State {
+    target: Option<Target>,
+    cancelled: bool,
+}
+
+/// Handle for aborting a `query_cancellable` call from another thread
+///
+/// ```python
+/// token = CancelToken()
+/// threading.Timer(5, token.cancel).start()
+/// rows = pool.query_cancellable("SELECT ...", token)
+/// ```
+#[pyclass(frozen)]
+#[derive(Default)]
+pub struct CancelToken {
+    state: Mutex<State>,
+}
+
+#[pymethods]
+impl CancelToken {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the server to cancel the running query
+    /// Cancelling before the query starts makes it fail immediately
+    fn cancel(&self, py: Python<'_>) -> PyResult<()> {
+        let target = {
+            let mut state = self.state.lock();
+            state.cancelled = true;
+            state.target.as_ref().map(|t| (t.token.clone(), t.tls.clone(), t.runtime.clone()))
+        };
+        let Some((token, tls, runtime)) = target else {
+            return Ok(());
+        };
+
+        py.allow_threads(|| {
+            runtime.block_on(async {
+                match tls {
+                    ClientTls::None => token.cancel_query(tokio_postgres::NoTls).await,
+                    ClientTls::Tls(tls) => token.cancel_query(tls).await,
+                }
+            })
+        })
+        .map_err(|e| PyRuntimeError::new_err(format!("Cancel request failed: {}", e)))
+    }
+
+    /// Whether cancel() has been called
+    #[getter]
+    fn cancelled(&self) -> bool {
+        self.state.lock().cancelled
+    }
+
+    fn __repr__(&self) -> String {
+        let state = self.state.lock();
+        format!("CancelToken(cancelled={}, running={})", state.cancelled, state.target.is_some())
+    }
+}
+
+impl CancelToken {
+    /// Point the token at the connection about to run a query
+    /// Returns false if the token was already cancelled
+    pub(crate) fn arm(&self, token: tokio_postgres::CancelToken, tls: ClientTls, runtime: Arc<tokio::runtime::Runtime>) -> bool {
+        let mut state = self.state.lock();
+        if state.cancelled {
+            return false;
+        }
+        state.target = Some(Target { token, tls, runtime });
+        true
+    }
+
+    /// Detach the token once the query has finished
+    pub(crate) fn disarm(&self) {
+        self.state.lock().target = None;
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.state.lock().cancelled
+    }
+}
@@ -0,0 +1,213 @@
+//! A DB-API 2.0 (PEP 249) cursor over a `Connection` or `PooledConnection`
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::types::PyList;
+use tokio_postgres::{Row, Statement};
+
+use crate::paramstyle::{bind_params, translate_format};
+use crate::pooled::PooledConnection;
+use crate::types::{row_to_tuple, DecodeOptions, PyValue};
+use crate::Connection;
+
+/// Something a cursor can run statements on
+pub(crate) trait CursorTarget {
+    /// Run a statement, returning its statement, rows and affected row count
+    fn run(&self, sql: &str, params: Vec<PyValue>) -> PyResult<(Statement, Vec<Row>, u64)>;
+    fn decode_options(&self) -> DecodeOptions;
+}
+
+/// The connection a cursor (or an advisory lock) belongs to
+pub(crate) enum Owner {
+    Connection(Py<Connection>),
+    Pooled(Py<PooledConnection>),
+}
+
+/// DB-API cursor: `execute`, then `fetchone`/`fetchmany`/`fetchall`
+///
+/// Placeholders use the `format` (`%s` with a sequence) or `pyformat`
+/// (`%(name)s` with a mapping) paramstyle; a literal `%` is written `%%`.
+/// Without parameters the statement is sent unchanged. Rows are tuples.
+#[pyclass]
+pub struct Cursor {
+    owner: Owner,
+    rows: Vec<PyObject>,
+    position: usize,
+    description: Option<PyObject>,
+    rowcount: i64,
+    closed: bool,
+    /// Rows returned by fetchmany() when no size is given
+    #[pyo3(get, set)]
+    arraysize: usize,
+}
+
+impl Cursor {
+    pub(crate) fn for_connection(connection: Py<Connection>) -> Self {
+        Self::new(Owner::Connection(connection))
+    }
+
+    pub(crate) fn for_pooled(connection: Py<PooledConnection>) -> Self {
+        Self::new(Owner::Pooled(connection))
+    }
+
+    fn new(owner: Owner) -> Self {
+        Cursor { owner, rows: Vec::new(), position: 0, description: None, rowcount: -1, closed: false, arraysize: 1 }
+    }
+
+    fn check_open(&self) -> PyResult<()> {
+        match self.closed {
+            true => Err(PyRuntimeError::new_err("Cursor closed")),
+            false => Ok(()),
+        }
+    }
+
+    /// Run one statement, rewriting its placeholders when parameters are given
+    fn run(&self, py: Python<'_>, sql: &str, params: Option<&Bound<'_, PyAny>>) -> PyResult<(Statement, Vec<Row>, u64)> {
+        let (sql, params) = match params {
+            Some(params) => {
+                let (sql, placeholders) = translate_format(sql)?;
+                (sql, bind_params(&placeholders, params)?)
+            }
+            None => (sql.to_string(), Vec::new()),
+        };
+        match &self.owner {
+            Owner::Connection(connection) => connection.bind(py).borrow().run(&sql, params),
+            Owner::Pooled(connection) => connection.bind(py).borrow().run(&sql, params),
+        }
+    }
+
+    fn decode_options(&self, py: Python<'_>) -> DecodeOptions {
+        match &self.owner {
+            Owner::Connection(connection) => connection.bind(py).borrow().decode_options(),
+            Owner::Pooled(connection) => connection.bind(py).borrow().decode_options(),
+        }
+    }
+
+    /// Rows not fetched yet; fetching before a statement that returns rows is an error
+    fn remaining(&self) -> PyResult<&[PyObject]> {
+        self.check_open()?;
+        if self.description.is_none() {
+            return Err(PyRuntimeError::new_err("No results to fetch; the last statement returned no rows"));
+        }
+        Ok(&self.rows[self.position..])
+    }
+}
+
+#[pymethods]
+impl Cursor {
+    /// Run a statement; its rows are then available from the fetch methods
+    #[pyo3(signature = (sql, params=None))]
+    fn execute(&mut self, py: Python<'_>, sql: &str, params: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        self.check_open()?;
+        let (statement, rows, affected) = self.run(py, sql, params)?;
+
+        let opts = self.decode_options(py);
+        self.rows = rows.iter()
+            .map(|row| row_to_tuple(py, row, &opts).map(|t| t.into_any().unbind()))
+            .collect::<PyResult<_>>()?;
+        self.position = 0;
+        self.rowcount = affected as i64;
+        // (name, type_code, display_size, internal_size, precision, scale, null_ok)
+        self.description = match statement.columns() {
+            [] => None,
+            columns => {
+                let entries = columns.iter().map(|c| -> PyObject {
+                    (c.name(), c.type_().oid(), py.None(), py.None(), py.None(), py.None(), py.None()).into_py(py)
+                });
+                Some(PyList::new_bound(py, entries).into_any().unbind())
+            }
+        };
+        Ok(())
+    }
+
+    /// Run a statement once per parameter set; rowcount is the total affected
+    fn executemany(&mut self, py: Python<'_>, sql: &str, seq_of_params: &Bound<'_, PyAny>) -> PyResult<()> {
+        let mut total = 0;
+        for params in seq_of_params.iter()? {
+            self.execute(py, sql, Some(&params?))?;
+            total += self.rowcount;
+        }
+        self.rows.clear();
+        self.description = None;
+        self.rowcount = total;
+        Ok(())
+    }
+
+    /// Next row as a tuple, or None when all rows were fetched
+    fn fetchone(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let row = self.remaining()?.first().map(|row| row.clone_ref(py));
+        if row.is_some() {
+            self.position += 1;
+        }
+        Ok(row)
+    }
+
+    /// Up to `size` rows (default `arraysize`)
+    #[pyo3(signature = (size=None))]
+    fn fetchmany<'py>(&mut self, py: Python<'py>, size: Option<usize>) -> PyResult<Bound<'py, PyList>> {
+        let size = size.unwrap_or(self.arraysize);
+        let rows = self.remaining()?.iter().take(size).map(|row| row.clone_ref(py)).collect::<Vec<_>>();
+        self.position += rows.len();
+        Ok(PyList::new_bound(py, rows))
+    }
+
+    /// All remaining rows
+    fn fetchall<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let rows = PyList::new_bound(py, self.remaining()?.iter().map(|row| row.clone_ref(py)));
+        self.position = self.rows.len();
+        Ok(rows)
+    }
+
+    /// Rows returned or affected by the last statement, -1 before the first one
+    #[getter]
+    fn rowcount(&self) -> i64 {
+        self.rowcount
+    }
+
+    /// One 7-item sequence per result column (name and type OID set, the rest None),
+    /// or None if the last statement returned no rows
+    #[getter]
+    fn description(&self, py: Python<'_>) -> Option<PyObject> {
+        self.description.as_ref().map(|d| d.clone_ref(py))
+    }
+
+    /// Release the buffered rows; the connection stays open
+    fn close(&mut self) {
+        self.closed = true;
+        self.rows.clear();
+        self.description = None;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Required by DB-API; sizes are not used
+    fn setinputsizes(&self, _sizes: &Bound<'_, PyAny>) {}
+
+    /// Required by DB-API; sizes are not used
+    #[pyo3(signature = (_size, _column=None))]
+    fn setoutputsize(&self, _size: &Bound<'_, PyAny>, _column: Option<&Bound<'_, PyAny>>) {}
+
+    fn __iter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        self.fetchone(py)
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&mut self, _exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) {
+        self.close();
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Cursor(rowcount={}, closed={})", self.rowcount, self.closed)
+    }
+}
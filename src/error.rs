@@ -1,6 +1,12 @@
 //! Error types for the database connector
 
+use deadpool_postgres::{PoolError, TimeoutType};
+use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyTimeoutError};
+use pyo3::prelude::*;
 use thiserror::Error;
+use tokio_postgres::error::{ErrorPosition, SqlState};
+
+use crate::paramstyle::redact_literals;
 
 #[derive(Error, Debug)]
 pub enum DbError {
@@ -13,9 +19,134 @@ pub enum DbError {
     #[error("Operation timed out: {0}")]
     Timeout(String),
 
+    #[error("Query cancelled")]
+    Cancelled,
+
+    #[error("Query returned more than max_rows={0} rows")]
+    TooManyRows(usize),
+
     #[error("Type conversion error: {0}")]
     TypeConversion(String),
 
+    #[allow(dead_code)]
     #[error("Configuration error: {0}")]
     Config(String),
 }
+
+impl DbError {
+    /// Whether the statement ran out of time, client-side or by the server's statement_timeout
+    pub fn is_statement_timeout(&self) -> bool {
+        match self {
+            DbError::Timeout(_) => true,
+            DbError::Query(e) => e.code() == Some(&SqlState::QUERY_CANCELED),
+            _ => false,
+        }
+    }
+}
+
+impl From<DbError> for PyErr {
+    /// Errors the server reported carry its SQLSTATE as a `sqlstate` attribute, e.g. "40001",
+    /// plus `position`, the 1-based character offset in the statement the error points at,
+    /// and `where`, the call stack of a function it was raised in (each None when not given)
+    fn from(err: DbError) -> PyErr {
+        let fields = match &err {
+            DbError::Query(e) => e.as_db_error().map(|db| {
+                let position = match db.position() {
+                    Some(ErrorPosition::Original(position)) => Some(*position),
+                    // Points into a query run inside a function, not the caller's statement
+                    Some(ErrorPosition::Internal { .. }) | None => None,
+                };
+                (db.code().code().to_string(), position, db.where_().map(str::to_string))
+            }),
+            _ => None,
+        };
+        let py_err = err.into_exception();
+        if let Some((sqlstate, position, where_)) = fields {
+            // Exception instances always take new attributes
+            Python::with_gil(|py| {
+                let value = py_err.value_bound(py);
+                let _ = value.setattr("sqlstate", sqlstate);
+                let _ = value.setattr("position", position);
+                let _ = value.setattr("where", where_);
+            });
+        }
+        py_err
+    }
+}
+
+/// How a failed statement is quoted in its error, from `error_sql_length` and `redact_error_sql`
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorSql {
+    /// Longest excerpt, in characters; 0 leaves the statement out of the message
+    pub max_length: usize,
+    /// Replace string literals, dollar-quoted ones included, with '?' in the excerpt
+    pub redact: bool,
+}
+
+impl ErrorSql {
+    /// Record `sql` on a server error or timeout as its `sql` attribute, the
+    /// text `position` counts into, and append an excerpt to the message
+    ///
+    /// Only the excerpt is redacted; the attribute always keeps the statement as sent.
+    /// Other errors are returned as they are.
+    pub fn attach(self, err: impl Into<PyErr>, sql: &str) -> PyErr {
+        let err = err.into();
+        Python::with_gil(|py| {
+            let value = err.value_bound(py);
+            if !value.hasattr("sqlstate").unwrap_or(false) && !err.is_instance_of::<PyTimeoutError>(py) {
+                return;
+            }
+            let _ = value.setattr("sql", sql);
+            if self.max_length > 0 {
+                let message = value.str().map(|m| m.to_string()).unwrap_or_default();
+                let _ = value.setattr("args", (format!("{} (SQL: {})", message, self.excerpt(sql)),));
+            }
+        });
+        err
+    }
+
+    /// `sql` on one line, cut to `max_length` characters
+    fn excerpt(self, sql: &str) -> String {
+        let sql = if self.redact { redact_literals(sql) } else { sql.to_string() };
+        let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        match sql.char_indices().nth(self.max_length) {
+            Some((end, _)) => format!("{}...", &sql[..end]),
+            None => sql,
+        }
+    }
+}
+
+impl DbError {
+    /// The Python exception this error is raised as
+    fn into_exception(self) -> PyErr {
+        match self {
+            DbError::Timeout(msg) => PyTimeoutError::new_err(msg),
+            // Cancelled by the server's statement_timeout
+            DbError::Query(e) if e.code() == Some(&SqlState::QUERY_CANCELED) => {
+                let reason = e.as_db_error().map_or(e.to_string(), |db| db.message().to_string());
+                PyTimeoutError::new_err(format!("Query timed out: {}", reason))
+            }
+            // A write in a READ ONLY transaction, e.g. transaction(read_only=True)
+            DbError::Query(e) if e.code() == Some(&SqlState::READ_ONLY_SQL_TRANSACTION) => {
+                let reason = e.as_db_error().map_or(e.to_string(), |db| db.message().to_string());
+                PyRuntimeError::new_err(format!("Write attempted in a read-only transaction: {}", reason))
+            }
+            DbError::Pool(PoolError::Timeout(TimeoutType::Wait)) => PyTimeoutError::new_err(
+                "Timed out waiting for a connection from the pool (see pool_size and pool_acquire_timeout_secs)",
+            ),
+            err @ DbError::Pool(PoolError::Timeout(_)) => PyTimeoutError::new_err(format!("Pool error: {}", err)),
+            DbError::Pool(e) => PyConnectionError::new_err(format!("Pool error: {}", e)),
+            // tokio_postgres only says "db error"; the server's own message is the useful part,
+            // and a client-side failure such as "error serializing parameter 0" names its cause
+            DbError::Query(e) => {
+                let reason = match (e.as_db_error(), std::error::Error::source(&e)) {
+                    (Some(db), _) => db.to_string(),
+                    (None, Some(source)) => format!("{}: {}", e, source),
+                    (None, None) => e.to_string(),
+                };
+                PyRuntimeError::new_err(format!("Query execution error: {}", reason))
+            }
+            err => PyRuntimeError::new_err(err.to_string()),
+        }
+    }
+}
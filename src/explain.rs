@@ -0,0 +1,61 @@
+//! `explain()`: a query's plan from `EXPLAIN`, as parsed JSON or text lines
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use tokio_postgres::{Row, Statement};
+
+use crate::types::{column_values, ColumnRef, DecodeOptions};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// The `EXPLAIN` options of one `explain()` call
+pub(crate) struct Explain {
+    format: Format,
+    analyze: bool,
+    verbose: bool,
+    buffers: bool,
+}
+
+impl Explain {
+    pub fn new(format: &str, analyze: bool, verbose: bool, buffers: bool) -> PyResult<Self> {
+        let format = match format.to_ascii_lowercase().as_str() {
+            "text" => Format::Text,
+            "json" => Format::Json,
+            _ => return Err(PyValueError::new_err(format!("Invalid format '{}'. Use 'json' or 'text'", format))),
+        };
+        Ok(Explain { format, analyze, verbose, buffers })
+    }
+
+    /// `sql` prefixed with `EXPLAIN` and the options that are on
+    /// Options left off aren't spelled out, so older servers accept the statement
+    pub fn statement(&self, sql: &str) -> String {
+        let mut options = vec![match self.format {
+            Format::Text => "FORMAT TEXT",
+            Format::Json => "FORMAT JSON",
+        }];
+        if self.analyze {
+            options.push("ANALYZE");
+        }
+        if self.verbose {
+            options.push("VERBOSE");
+        }
+        if self.buffers {
+            options.push("BUFFERS");
+        }
+        format!("EXPLAIN ({}) {}", options.join(", "), sql)
+    }
+
+    /// The plan: the parsed JSON document, or one string per line of text
+    pub fn plan<'py>(&self, py: Python<'py>, statement: &Statement, rows: &[Row], decode: &DecodeOptions) -> PyResult<Bound<'py, PyAny>> {
+        let values = column_values(py, statement.columns(), rows, &ColumnRef::Index(0), decode)?;
+        match self.format {
+            Format::Text => Ok(values.into_any()),
+            // A JSON plan is a single row holding the whole document
+            Format::Json => values.get_item(0),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Primary/standby pool pair with automatic failover
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyConnectionError, PyTimeoutError, PyValueError};
+use pyo3::types::{PyDict, PyTuple};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use crate::AsyncPool;
+
+/// Two pools behind one interface: writes go to the current primary, and the
+/// standby is promoted once the primary fails `failure_threshold` consecutive
+/// health checks
+#[pyclass]
+pub struct FailoverPool {
+    pools: [Py<AsyncPool>; 2],
+    /// Index into `pools` of the current primary
+    primary: AtomicUsize,
+    failures: AtomicU32,
+    failovers: AtomicU32,
+    failure_threshold: u32,
+    read_from_standby: bool,
+}
+
+#[pymethods]
+impl FailoverPool {
+    #[new]
+    #[pyo3(signature = (primary, standby, failure_threshold=3, read_from_standby=false))]
+    fn new(primary: Py<AsyncPool>, standby: Py<AsyncPool>, failure_threshold: u32, read_from_standby: bool) -> PyResult<Self> {
+        if failure_threshold == 0 {
+            return Err(PyValueError::new_err("failure_threshold must be at least 1"));
+        }
+        Ok(FailoverPool {
+            pools: [primary, standby],
+            primary: AtomicUsize::new(0),
+            failures: AtomicU32::new(0),
+            failovers: AtomicU32::new(0),
+            failure_threshold,
+            read_from_standby,
+        })
+    }
+
+    /// The pool currently serving writes
+    #[getter]
+    fn primary(&self, py: Python<'_>) -> Py<AsyncPool> {
+        self.pools[self.primary_index()].clone_ref(py)
+    }
+
+    /// The pool held in reserve
+    #[getter]
+    fn standby(&self, py: Python<'_>) -> Py<AsyncPool> {
+        self.pools[1 - self.primary_index()].clone_ref(py)
+    }
+
+    /// Number of failovers performed so far
+    #[getter]
+    fn failover_count(&self) -> u32 {
+        self.failovers.load(Ordering::SeqCst)
+    }
+
+    /// Execute a query and return rows (routed to the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn query(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "query", args, kwargs, false)
+    }
+
+    /// Execute a query returning `{column: [values...]}` (routed to the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn query_columns(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "query_columns", args, kwargs, false)
+    }
+
+    /// Execute a query returning one column's values (routed to the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn fetch_column(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "fetch_column", args, kwargs, false)
+    }
+
+    /// Execute a query returning a pandas DataFrame (routed to the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn query_df(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "query_df", args, kwargs, false)
+    }
+
+    /// Execute a query returning a pyarrow.RecordBatch (routed to the standby when read_from_standby is set)
+    #[cfg(feature = "arrow")]
+    #[pyo3(signature = (*args, **kwargs))]
+    fn query_arrow(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "query_arrow", args, kwargs, false)
+    }
+
+    /// Fetch a single row (routed to the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn fetch_one(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "fetch_one", args, kwargs, false)
+    }
+
+    /// Count a query's rows on the server (on the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn row_count(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "row_count", args, kwargs, false)
+    }
+
+    /// The plan of a query from `EXPLAIN` (on the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn explain(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "explain", args, kwargs, false)
+    }
+
+    /// Iterate over a query's rows through a server-side cursor (on the standby when read_from_standby is set)
+    #[pyo3(signature = (*args, **kwargs))]
+    fn iter_rows(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "iter_rows", args, kwargs, false)
+    }
+
+    /// Execute a statement on the primary
+    #[pyo3(signature = (*args, **kwargs))]
+    fn execute(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "execute", args, kwargs, true)
+    }
+
+    /// Execute on the primary, returning the command tag
+    #[pyo3(signature = (*args, **kwargs))]
+    fn execute_with_tag(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "execute_with_tag", args, kwargs, true)
+    }
+
+    /// Insert or update a row on the primary
+    #[pyo3(signature = (*args, **kwargs))]
+    fn upsert(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "upsert", args, kwargs, true)
+    }
+
+    /// Execute many statements in a transaction on the primary
+    #[pyo3(signature = (*args, **kwargs))]
+    fn execute_many(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "execute_many", args, kwargs, true)
+    }
+
+    /// Bulk upsert in a transaction on the primary
+    #[pyo3(signature = (*args, **kwargs))]
+    fn bulk_upsert(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "bulk_upsert", args, kwargs, true)
+    }
+
+    /// Bulk insert on the primary
+    #[pyo3(signature = (*args, **kwargs))]
+    fn execute_batch(&self, py: Python<'_>, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        self.route(py, "execute_batch", args, kwargs, true)
+    }
+
+    /// Probe the primary, promoting the standby once the failure threshold is reached
+    /// Returns True if a failover happened
+    fn check_health(&self, py: Python<'_>) -> PyResult<bool> {
+        let primary = self.pools[self.primary_index()].bind(py);
+        if primary.call_method0("is_healthy")?.extract::<bool>()? {
+            self.failures.store(0, Ordering::SeqCst);
+            return Ok(false);
+        }
+
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures < self.failure_threshold {
+            return Ok(false);
+        }
+        self.promote_standby(py)
+    }
+
+    /// Promote the standby if it is healthy. Returns True if the roles were swapped
+    fn failover(&self, py: Python<'_>) -> PyResult<bool> {
+        self.promote_standby(py)
+    }
+
+    /// Close both pools
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        for pool in &self.pools {
+            pool.bind(py).call_method0("close")?;
+        }
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FailoverPool(primary={}, failures={}, failovers={})",
+            if self.primary_index() == 0 { "original" } else { "standby" },
+            self.failures.load(Ordering::SeqCst),
+            self.failovers.load(Ordering::SeqCst)
+        )
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&self, py: Python<'_>, _exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) -> PyResult<()> {
+        self.close(py)
+    }
+}
+
+impl FailoverPool {
+    fn primary_index(&self) -> usize {
+        self.primary.load(Ordering::SeqCst)
+    }
+
+    /// Call `method` on the pool responsible for it, failing over on connection-level errors
+    fn route(&self, py: Python<'_>, method: &str, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>, write: bool) -> PyResult<PyObject> {
+        let primary = self.primary_index();
+        let target = if write || !self.read_from_standby { primary } else { 1 - primary };
+
+        match self.pools[target].bind(py).call_method(method, args, kwargs) {
+            Ok(result) => Ok(result.unbind()),
+            Err(e) if is_connection_error(py, &e) => {
+                // A read on the standby falls back to the primary
+                let failed_over = target == primary && self.check_health(py)?;
+                if target != primary || failed_over {
+                    let pool = self.pools[self.primary_index()].bind(py);
+                    return pool.call_method(method, args, kwargs).map(Bound::unbind);
+                }
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn promote_standby(&self, py: Python<'_>) -> PyResult<bool> {
+        let primary = self.primary_index();
+        let standby = self.pools[1 - primary].bind(py);
+        if !standby.call_method0("is_healthy")?.extract::<bool>()? {
+            return Ok(false);
+        }
+
+        let promoted = self.primary
+            .compare_exchange(primary, 1 - primary, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok();
+        if promoted {
+            self.failures.store(0, Ordering::SeqCst);
+            self.failovers.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(promoted)
+    }
+}
+
+fn is_connection_error(py: Python<'_>, err: &PyErr) -> bool {
+    err.is_instance_of::<PyConnectionError>(py) || err.is_instance_of::<PyTimeoutError>(py)
+}
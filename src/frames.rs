@@ -0,0 +1,68 @@
+//! Building pandas DataFrames from result rows
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyImportError;
+use pyo3::types::{PyDict, PyList};
+use tokio_postgres::types::Type;
+use tokio_postgres::{Column, Row};
+
+use crate::types::{column_keys, rows_to_columns, DecodeOptions, TimestampOutput};
+
+/// pandas dtype for a column; `None` keeps the Python objects (text, Decimal, dates, ...)
+///
+/// Integers and booleans use pandas' nullable dtypes so NULLs become `pd.NA`
+/// without turning the column into floats; float NULLs become NaN.
+fn pandas_dtype(ty: &Type, opts: &DecodeOptions) -> Option<&'static str> {
+    match *ty {
+        Type::BOOL => Some("boolean"),
+        Type::INT2 => Some("Int16"),
+        Type::INT4 => Some("Int32"),
+        Type::INT8 => Some("Int64"),
+        Type::FLOAT4 => Some("float32"),
+        Type::FLOAT8 => Some("float64"),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => match opts.timestamp_output {
+            TimestampOutput::DateTime => None,
+            TimestampOutput::Epoch => Some("float64"),
+            TimestampOutput::EpochMicros => Some("Int64"),
+        },
+        _ => None,
+    }
+}
+
+/// Build one DataFrame column from its decoded values
+fn to_series<'py>(pd: &Bound<'py, PyModule>, ty: &Type, opts: &DecodeOptions, values: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let is_timestamp = matches!(*ty, Type::TIMESTAMP | Type::TIMESTAMPTZ);
+    if is_timestamp && opts.timestamp_output == TimestampOutput::DateTime {
+        let kwargs = PyDict::new_bound(pd.py());
+        kwargs.set_item("utc", *ty == Type::TIMESTAMPTZ)?;
+        // datetime64[ns] can't hold every Postgres timestamp; keep those columns as objects
+        if let Ok(datetimes) = pd.call_method("to_datetime", (&values,), Some(&kwargs)) {
+            return pd.call_method1("Series", (datetimes,));
+        }
+        return pd.call_method1("Series", (values,));
+    }
+
+    let kwargs = PyDict::new_bound(pd.py());
+    kwargs.set_item("dtype", pandas_dtype(ty, opts).unwrap_or("object"))?;
+    pd.call_method("Series", (values,), Some(&kwargs))
+}
+
+/// Convert result rows to a `pandas.DataFrame`, one typed column at a time
+pub fn rows_to_dataframe<'py>(py: Python<'py>, columns: &[Column], rows: &[Row], opts: &DecodeOptions) -> PyResult<Bound<'py, PyAny>> {
+    let pd = py.import_bound("pandas")
+        .map_err(|_| PyImportError::new_err("query_df requires pandas (pip install pandas)"))?;
+    let values = rows_to_columns(py, columns, rows, opts)?;
+
+    let keys = column_keys(columns);
+    let data = PyDict::new_bound(py);
+    for (column, key) in columns.iter().zip(&keys) {
+        if let Some(column_values) = values.get_item(key.as_ref())? {
+            data.set_item(key.as_ref(), to_series(&pd, column.type_(), opts, column_values)?)?;
+        }
+    }
+
+    let kwargs = PyDict::new_bound(py);
+    let names = PyList::new_bound(py, keys.iter().map(|key| key.as_ref()));
+    kwargs.set_item("columns", names)?;
+    pd.call_method("DataFrame", (data,), Some(&kwargs))
+}
@@ -0,0 +1,96 @@
+//! Session-level advisory locks (`pg_advisory_lock` and friends)
+//!
+//! Advisory locks belong to the backend that took them, so they're only
+//! offered on a `Connection` or a `PooledConnection` from `acquire()`.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+
+use crate::cursor::{CursorTarget, Owner};
+use crate::types::PyValue;
+
+/// A lock key: one bigint, or a pair of int4s (a separate key space on the server)
+#[derive(FromPyObject, Clone, Copy, Debug)]
+pub enum LockKey {
+    Single(i64),
+    Pair(i32, i32),
+}
+
+impl std::fmt::Display for LockKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockKey::Single(key) => write!(f, "{}", key),
+            LockKey::Pair(a, b) => write!(f, "({}, {})", a, b),
+        }
+    }
+}
+
+/// Call the advisory lock function `function` with `key`, returning its boolean result
+///
+/// `pg_advisory_lock` returns void and always reports true once it has the lock.
+pub(crate) fn call(target: &impl CursorTarget, function: &str, key: LockKey) -> PyResult<bool> {
+    let (sql, params) = match key {
+        LockKey::Single(key) => (format!("SELECT {}($1)", function), vec![PyValue::Int(key)]),
+        LockKey::Pair(a, b) => (format!("SELECT {}($1, $2)", function), vec![PyValue::Int(a.into()), PyValue::Int(b.into())]),
+    };
+    let (statement, rows, _) = target.run(&sql, params)?;
+    match statement.columns()[0].type_() == &tokio_postgres::types::Type::BOOL {
+        true => Ok(rows[0].get(0)),
+        false => Ok(true),
+    }
+}
+
+/// A held advisory lock, released by `release()` or at the end of a `with` block
+///
+/// Dropping it without either leaves the lock held until the session ends or
+/// `advisory_unlock()` is called.
+#[pyclass]
+pub struct AdvisoryLock {
+    owner: Owner,
+    key: LockKey,
+    held: bool,
+}
+
+impl AdvisoryLock {
+    pub(crate) fn new(owner: Owner, key: LockKey) -> Self {
+        AdvisoryLock { owner, key, held: true }
+    }
+}
+
+#[pymethods]
+impl AdvisoryLock {
+    /// Release the lock; a no-op if it was already released
+    fn release(&mut self, py: Python<'_>) -> PyResult<()> {
+        if !self.held {
+            return Ok(());
+        }
+        self.held = false;
+        let released = match &self.owner {
+            Owner::Connection(connection) => call(&*connection.bind(py).borrow(), "pg_advisory_unlock", self.key)?,
+            Owner::Pooled(connection) => call(&*connection.bind(py).borrow(), "pg_advisory_unlock", self.key)?,
+        };
+        match released {
+            true => Ok(()),
+            false => Err(PyRuntimeError::new_err(format!("Advisory lock {} was not held by this session", self.key))),
+        }
+    }
+
+    /// Whether the lock hasn't been released through this object
+    #[getter]
+    fn held(&self) -> bool {
+        self.held
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&mut self, py: Python<'_>, _exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) -> PyResult<()> {
+        self.release(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AdvisoryLock(key={}, held={})", self.key, self.held)
+    }
+}
@@ -0,0 +1,50 @@
+//! Cumulative statement counters for `AsyncPool.metrics()`
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::error::DbError;
+
+/// Statement counts and timings over a pool's lifetime, updated without locks
+#[derive(Default)]
+pub(crate) struct QueryMetrics {
+    /// Statements that ran to completion on the server, successfully or not
+    queries: AtomicU64,
+    /// Calls that failed for any reason, timeouts included
+    errors: AtomicU64,
+    timeouts: AtomicU64,
+    total_time_us: AtomicU64,
+    max_time_us: AtomicU64,
+}
+
+impl QueryMetrics {
+    /// Count a statement that took `elapsed` on its connection
+    pub fn record_latency(&self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.total_time_us.fetch_add(micros, Ordering::Relaxed);
+        self.max_time_us.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Count a failed call
+    pub fn record_outcome<T>(&self, result: &Result<T, DbError>) {
+        if let Err(err) = result {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            if err.is_statement_timeout() {
+                self.timeouts.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("queries", self.queries.load(Ordering::Relaxed))?;
+        dict.set_item("errors", self.errors.load(Ordering::Relaxed))?;
+        dict.set_item("timeouts", self.timeouts.load(Ordering::Relaxed))?;
+        dict.set_item("total_time_ms", self.total_time_us.load(Ordering::Relaxed) as f64 / 1000.0)?;
+        dict.set_item("max_time_ms", self.max_time_us.load(Ordering::Relaxed) as f64 / 1000.0)?;
+        Ok(dict)
+    }
+}
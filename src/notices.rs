@@ -0,0 +1,152 @@
+//! Server notices (`RAISE NOTICE`, "relation already exists, skipping", ...) as Python warnings
+//!
+//! Notices arrive on the task driving the connection, which must not wait for
+//! the GIL: the thread holding it may be blocked on that very connection. So
+//! they're queued there and raised as `UserWarning`s by the thread that ran
+//! the statement, once it returns.
+//!
+//! Each connection has its own queue, so a pool call only warns about the
+//! notices raised on the connection it checked out.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyUserWarning;
+use futures_util::stream::{self, StreamExt};
+use ahash::AHashMap;
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::sync::{Arc, Weak};
+use tokio::task::JoinHandle;
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+use tokio_postgres::{AsyncMessage, Client, Socket};
+
+use crate::status::ParameterStatus;
+
+/// Notices received on one connection, waiting to be warned about
+#[derive(Clone, Default)]
+pub(crate) struct NoticeQueue(Arc<Mutex<Vec<String>>>);
+
+impl NoticeQueue {
+    fn push(&self, notice: &tokio_postgres::error::DbError) {
+        let mut text = format!("{}: {}", notice.severity(), notice.message());
+        if let Some(detail) = notice.detail() {
+            text.push_str(&format!("\nDETAIL: {}", detail));
+        }
+        if let Some(hint) = notice.hint() {
+            text.push_str(&format!("\nHINT: {}", hint));
+        }
+        self.0.lock().push(text);
+    }
+
+    /// Raise each queued notice as a `UserWarning`, attributed to the calling Python code
+    ///
+    /// The statement has already completed, so a warning the filters turn into
+    /// an error is reported as unraisable rather than failing it.
+    pub fn warn(&self) {
+        let notices = std::mem::take(&mut *self.0.lock());
+        if notices.is_empty() {
+            return;
+        }
+        Python::with_gil(|py| {
+            let category = py.get_type_bound::<PyUserWarning>();
+            for notice in notices {
+                if let Err(err) = PyErr::warn_bound(py, &category, &notice, 1) {
+                    err.write_unraisable_bound(py, None);
+                }
+            }
+        });
+    }
+
+    /// Drive `connection` until it closes, queueing the notices it receives
+    /// and keeping `status`, if given, in step with its reported parameters
+    pub async fn drive<S, T>(self, mut connection: tokio_postgres::Connection<S, T>, status: Option<ParameterStatus>)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+        T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let mut messages = stream::poll_fn(move |cx| {
+            // Locked first, so a caller woken by a response this poll delivers
+            // reads the parameters only once they're refreshed
+            let mut values = status.as_ref().map(ParameterStatus::lock);
+            let message = connection.poll_message(cx);
+            if let Some(values) = &mut values {
+                values.refresh(|name| connection.parameter(name));
+            }
+            message
+        });
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(AsyncMessage::Notice(notice)) => self.push(&notice),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Connection error: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// The queue of the connection `NoticeConnect` just opened on this thread,
+    /// until `PoolNotices::register` claims it for the pooled client
+    static CONNECTED: RefCell<Option<NoticeQueue>> = const { RefCell::new(None) };
+}
+
+/// A pooled connection's notice queue, tied to the lifetime of the connection
+type ConnectionEntry = (Weak<deadpool_postgres::StatementCache>, NoticeQueue);
+
+/// Per-connection notice queues for a pool
+///
+/// Connections are identified like in `PoolStatementCache`, by deadpool's own
+/// per-client statement cache `Arc`.
+#[derive(Clone, Default)]
+pub(crate) struct PoolNotices(Arc<Mutex<AHashMap<usize, ConnectionEntry>>>);
+
+impl PoolNotices {
+    /// Tie the queue `NoticeConnect` opened a new connection with to its pooled client
+    ///
+    /// Runs as the pool's first `post_create` hook. deadpool calls it in the same
+    /// poll that finished the connect, so the queue is still in this thread's slot.
+    pub fn register(&self, client: &deadpool_postgres::ClientWrapper) {
+        let queue = CONNECTED.with(|connected| connected.borrow_mut().take()).unwrap_or_default();
+        let mut queues = self.0.lock();
+        // Forget queues of connections that have since closed
+        queues.retain(|_, (conn, _)| conn.strong_count() > 0);
+        queues.insert(Arc::as_ptr(&client.statement_cache) as usize, (Arc::downgrade(&client.statement_cache), queue));
+    }
+
+    /// The queue of a checked-out connection, emptied of what its previous checkout left
+    pub fn for_connection(&self, client: &deadpool_postgres::Object) -> NoticeQueue {
+        let key = Arc::as_ptr(&client.statement_cache) as usize;
+        let queue = self.0.lock().get(&key).map(|(_, queue)| queue.clone()).unwrap_or_default();
+        queue.0.lock().clear();
+        queue
+    }
+}
+
+/// Opens pool connections, each driven with its own notice queue
+pub(crate) struct NoticeConnect<T> {
+    pub tls: T,
+}
+
+impl<T> deadpool_postgres::Connect for NoticeConnect<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    fn connect(
+        &self,
+        pg_config: &tokio_postgres::Config,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(Client, JoinHandle<()>), tokio_postgres::Error>> + Send + '_>> {
+        let tls = self.tls.clone();
+        let pg_config = pg_config.clone();
+        Box::pin(async move {
+            let (client, connection) = pg_config.connect(tls).await?;
+            let notices = NoticeQueue::default();
+            CONNECTED.with(|connected| *connected.borrow_mut() = Some(notices.clone()));
+            Ok((client, tokio::spawn(notices.drive(connection, None))))
+        })
+    }
+}
@@ -0,0 +1,351 @@
+//! Rewriting DB-API placeholders (`%s`, `%(name)s`, `:name`) to Postgres' `$1` style
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyTypeError, PyValueError};
+use pyo3::types::{PyList, PyMapping, PyString};
+
+use crate::types::PyValue;
+
+/// What the placeholders in a rewritten statement refer to
+#[derive(Debug, PartialEq)]
+pub enum Placeholders {
+    /// `%s`: the n-th parameter of a sequence
+    Positional(usize),
+    /// `%(name)s` or `:name`: keys of a mapping, in `$n` order
+    Named(Vec<String>),
+}
+
+/// `$n` for a named placeholder, reusing the number of an earlier use of the name
+fn named_index(names: &mut Vec<String>, name: &str) -> usize {
+    match names.iter().position(|n| n == name) {
+        Some(index) => index + 1,
+        None => {
+            names.push(name.to_string());
+            names.len()
+        }
+    }
+}
+
+/// Length of the quoted section (string literal, quoted identifier, comment or
+/// dollar-quoted string) starting at `start`, or `None` if none starts there
+fn quoted_len(sql: &str, start: usize) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let rest = &sql[start..];
+    let closing = |from: usize, pattern: &str| rest[from..].find(pattern).map_or(rest.len(), |i| from + i + pattern.len());
+    match bytes[start] {
+        b'\'' => {
+            // E'...' strings escape quotes with backslashes
+            let escapes = start > 0
+                && bytes[start - 1].eq_ignore_ascii_case(&b'e')
+                && (start < 2 || !(bytes[start - 2].is_ascii_alphanumeric() || bytes[start - 2] == b'_'));
+            let mut i = 1;
+            while i < rest.len() {
+                match bytes[start + i] {
+                    b'\\' if escapes => i += 1,
+                    b'\'' => return Some(i + 1),
+                    _ => {}
+                }
+                i += 1;
+            }
+            Some(rest.len())
+        }
+        b'"' => Some(closing(1, "\"")),
+        b'-' if rest.starts_with("--") => Some(closing(2, "\n")),
+        b'/' if rest.starts_with("/*") => Some(closing(2, "*/")),
+        b'$' => {
+            // $tag$ ... $tag$, where the tag can't start with a digit ($1 is a parameter)
+            let preceded_by_word = start > 0 && (bytes[start - 1].is_ascii_alphanumeric() || bytes[start - 1] == b'_');
+            let tag_len = rest[1..].find('$')?;
+            let tag = &rest[1..1 + tag_len];
+            let valid_tag = tag.chars().next().is_none_or(|c| c.is_alphabetic() || c == '_')
+                && tag.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if preceded_by_word || !valid_tag {
+                return None;
+            }
+            let delimiter = &rest[..tag_len + 2];
+            Some(closing(delimiter.len(), delimiter))
+        }
+        _ => None,
+    }
+}
+
+/// Rewrite `%s` or `%(name)s` placeholders to `$n`, leaving literals, quoted
+/// identifiers and comments alone; `%%` stands for a literal `%`
+///
+/// A name used more than once maps to the same `$n`. Mixing both styles is an error.
+pub fn translate_format(sql: &str) -> PyResult<(String, Placeholders)> {
+    let mut out = String::with_capacity(sql.len());
+    let mut positional = 0usize;
+    let mut names: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(len) = quoted_len(sql, i) {
+            out.push_str(&sql[i..i + len]);
+            i += len;
+            continue;
+        }
+        let c = sql[i..].chars().next().expect("in bounds");
+        if c != '%' {
+            out.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        let rest = &sql[i + 1..];
+        if rest.starts_with('%') {
+            out.push('%');
+            i += 2;
+        } else if rest.starts_with('s') {
+            positional += 1;
+            out.push_str(&format!("${}", positional));
+            i += 2;
+        } else if let Some(named) = rest.strip_prefix('(') {
+            let name = named.split_once(")s").map(|(name, _)| name).ok_or_else(|| {
+                PyValueError::new_err(format!("Unterminated placeholder '%{}'", rest.chars().take(20).collect::<String>()))
+            })?;
+            out.push_str(&format!("${}", named_index(&mut names, name)));
+            i += name.len() + 4;
+        } else {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported placeholder '%{}'; write a literal % as %%",
+                rest.chars().next().unwrap_or(' ')
+            )));
+        }
+    }
+
+    match (positional, names.is_empty()) {
+        (0, false) => Ok((out, Placeholders::Named(names))),
+        (_, true) => Ok((out, Placeholders::Positional(positional))),
+        _ => Err(PyValueError::new_err("Cannot mix %s and %(name)s placeholders")),
+    }
+}
+
+/// Rewrite `:name` placeholders to `$n`, leaving `::` casts, literals, quoted
+/// identifiers and comments alone
+pub fn translate_named(sql: &str) -> (String, Placeholders) {
+    let mut out = String::with_capacity(sql.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(len) = quoted_len(sql, i) {
+            out.push_str(&sql[i..i + len]);
+            i += len;
+            continue;
+        }
+        let c = sql[i..].chars().next().expect("in bounds");
+        let rest = &sql[i + c.len_utf8()..];
+        if c == ':' && rest.starts_with(':') {
+            out.push_str("::");
+            i += 2;
+            continue;
+        }
+        let name_len = rest
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(rest.len());
+        let starts_name = rest.chars().next().is_some_and(|ch| ch.is_alphabetic() || ch == '_');
+        if c == ':' && starts_name {
+            out.push_str(&format!("${}", named_index(&mut names, &rest[..name_len])));
+            i += 1 + name_len;
+        } else {
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    (out, Placeholders::Named(names))
+}
+
+/// `sql` with each string literal, dollar-quoted ones included, replaced by `'?'`,
+/// for quoting statements whose values may be secret
+pub fn redact_literals(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(len) = quoted_len(sql, i) {
+            match sql.as_bytes()[i] {
+                b'\'' | b'$' => out.push_str("'?'"),
+                _ => out.push_str(&sql[i..i + len]),
+            }
+            i += len;
+            continue;
+        }
+        let c = sql[i..].chars().next().expect("in bounds");
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// Highest `$n` placeholder in `sql` outside literals, quoted identifiers and
+/// comments; Postgres expects that many parameters even if some numbers are skipped
+fn max_placeholder(sql: &str) -> usize {
+    let bytes = sql.as_bytes();
+    let mut max = 0;
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(len) = quoted_len(sql, i) {
+            i += len;
+            continue;
+        }
+        let preceded_by_word = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        if bytes[i] == b'$' && !preceded_by_word {
+            let digits = sql[i + 1..].find(|ch: char| !ch.is_ascii_digit()).unwrap_or(sql.len() - i - 1);
+            if let Ok(number) = sql[i + 1..i + 1 + digits].parse::<usize>() {
+                max = max.max(number);
+            }
+            i += 1 + digits;
+            continue;
+        }
+        i += sql[i..].chars().next().map_or(1, char::len_utf8);
+    }
+    max
+}
+
+/// Fail before anything is sent when `given` parameters don't match the statement's `$n` placeholders
+pub fn check_param_count(sql: &str, given: usize) -> PyResult<()> {
+    let expected = max_placeholder(sql);
+    if expected == given {
+        return Ok(());
+    }
+    Err(PyValueError::new_err(format!(
+        "query expects {} parameter{} but {} {} provided",
+        expected,
+        if expected == 1 { "" } else { "s" },
+        given,
+        if given == 1 { "was" } else { "were" }
+    )))
+}
+
+/// Rewrite `sql` for `paramstyle` and order `params` to match, giving `$n` SQL and positional values
+///
+/// Without a paramstyle the statement already uses `$n` and `params` must be a sequence.
+pub fn bind(sql: &str, params: Option<&Bound<'_, PyAny>>, paramstyle: Option<&str>) -> PyResult<(String, Vec<PyValue>)> {
+    let (sql, placeholders) = match paramstyle {
+        None => {
+            let params = match params {
+                Some(params) if params.downcast::<PyMapping>().is_ok() => {
+                    return Err(PyTypeError::new_err("A mapping of parameters needs paramstyle='named' or 'pyformat'"));
+                }
+                Some(params) => params.extract()?,
+                None => Vec::new(),
+            };
+            return Ok((sql.to_string(), params));
+        }
+        Some("format" | "pyformat") => translate_format(sql)?,
+        Some("named") => translate_named(sql),
+        Some(other) => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid paramstyle '{}'. Use 'format', 'pyformat' or 'named'",
+                other
+            )))
+        }
+    };
+    let values = match (params, &placeholders) {
+        (Some(params), _) => bind_params(&placeholders, params)?,
+        (None, Placeholders::Positional(0)) => Vec::new(),
+        (None, Placeholders::Named(names)) if names.is_empty() => Vec::new(),
+        (None, _) => return Err(PyTypeError::new_err("The statement has placeholders but no parameters were given")),
+    };
+    Ok((sql, values))
+}
+
+/// Order `params` (a sequence or a mapping) to match the rewritten statement's `$n`
+pub fn bind_params(placeholders: &Placeholders, params: &Bound<'_, PyAny>) -> PyResult<Vec<PyValue>> {
+    match placeholders {
+        Placeholders::Positional(count) => {
+            if params.downcast::<PyMapping>().is_ok() {
+                return match count {
+                    0 => Ok(Vec::new()),
+                    _ => Err(PyTypeError::new_err("A mapping of parameters needs %(name)s placeholders")),
+                };
+            }
+            if params.is_instance_of::<PyString>() {
+                return Err(PyTypeError::new_err("Parameters must be a sequence, not a string"));
+            }
+            let values: Vec<PyValue> = params.extract()?;
+            if values.len() != *count {
+                return Err(PyTypeError::new_err(format!(
+                    "The statement has {} placeholders but {} parameters were given",
+                    count,
+                    values.len()
+                )));
+            }
+            Ok(values)
+        }
+        Placeholders::Named(names) => {
+            let mapping = params.downcast::<PyMapping>()
+                .map_err(|_| PyTypeError::new_err("%(name)s placeholders need a mapping of parameters"))?;
+            names.iter()
+                .map(|name| match mapping.get_item(name) {
+                    Ok(value) => value.extract(),
+                    Err(_) => Err(PyKeyError::new_err(name.clone())),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Expand the `$n` placeholder for a list into one placeholder per item, e.g.
+/// `IN ($1)` with `[[1, 2, 3]]` becomes `IN ($1, $2, $3)` with `[1, 2, 3]`
+///
+/// Later placeholders are renumbered to match the flattened parameters. An empty
+/// list becomes `NULL`, so `IN ($1)` matches nothing.
+#[pyfunction]
+pub fn expand_in<'py>(
+    py: Python<'py>,
+    sql: &str,
+    param_index: usize,
+    params: &Bound<'py, PyAny>,
+) -> PyResult<(String, Bound<'py, PyList>)> {
+    if params.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err("Parameters must be a sequence, not a string"));
+    }
+    let params = params.iter()?.collect::<PyResult<Vec<_>>>()?;
+    let list = param_index.checked_sub(1).and_then(|i| params.get(i)).ok_or_else(|| {
+        PyIndexError::new_err(format!("param_index {} is out of range for {} parameters", param_index, params.len()))
+    })?;
+    if list.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err(format!("Parameter ${} must be a sequence, not a string", param_index)));
+    }
+    let items = list.iter()?.collect::<PyResult<Vec<_>>>()?;
+
+    let expansion = match items.len() {
+        0 => "NULL".to_string(),
+        n => (param_index..param_index + n).map(|i| format!("${}", i)).collect::<Vec<_>>().join(", "),
+    };
+
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len() + expansion.len());
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(len) = quoted_len(sql, i) {
+            out.push_str(&sql[i..i + len]);
+            i += len;
+            continue;
+        }
+        let c = sql[i..].chars().next().expect("in bounds");
+        let preceded_by_word = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        // Only past a '$' is i + 1 sure to be a char boundary
+        let digits = match c == '$' && !preceded_by_word {
+            true => sql[i + 1..].find(|ch: char| !ch.is_ascii_digit()).unwrap_or(sql.len() - i - 1),
+            false => 0,
+        };
+        if digits == 0 {
+            out.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        let number: usize = sql[i + 1..i + 1 + digits].parse()
+            .map_err(|_| PyValueError::new_err(format!("Invalid placeholder '{}'", &sql[i..i + 1 + digits])))?;
+        match number.cmp(&param_index) {
+            std::cmp::Ordering::Less => out.push_str(&sql[i..i + 1 + digits]),
+            std::cmp::Ordering::Equal => out.push_str(&expansion),
+            std::cmp::Ordering::Greater => out.push_str(&format!("${}", number + items.len() - 1)),
+        }
+        i += 1 + digits;
+    }
+
+    let mut flattened = params;
+    flattened.splice(param_index - 1..param_index, items);
+    Ok((out, PyList::new_bound(py, flattened)))
+}
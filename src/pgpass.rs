@@ -0,0 +1,70 @@
+//! Password lookup in libpq's password file (~/.pgpass)
+
+use std::fs;
+use std::path::PathBuf;
+
+/// PGPASSFILE, else ~/.pgpass (%APPDATA%\postgresql\pgpass.conf on Windows)
+fn password_file() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("PGPASSFILE").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    if cfg!(windows) {
+        std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("postgresql").join("pgpass.conf"))
+    } else {
+        std::env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".pgpass"))
+    }
+}
+
+/// Split a `host:port:database:user:password` line, honouring `\:` and `\\` escapes
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Find the password for a connection in the password file, the way libpq does
+///
+/// Returns an error describing why the file was ignored when it is readable by
+/// group or others; like libpq, such a file is never used.
+pub fn lookup(host: &str, port: u16, database: &str, user: &str) -> Result<Option<String>, String> {
+    let Some(path) = password_file() else {
+        return Ok(None);
+    };
+    let Ok(metadata) = fs::metadata(&path) else {
+        return Ok(None);
+    };
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o077 != 0 {
+            return Err(format!(
+                "password file \"{}\" has group or world access; permissions should be u=rw (0600) or less",
+                path.display()
+            ));
+        }
+    }
+
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let port = port.to_string();
+    let wanted = [host, port.as_str(), database, user];
+    let password = contents.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(split_fields)
+        .filter(|fields| fields.len() >= 5)
+        .find(|fields| wanted.iter().zip(fields).all(|(value, pattern)| pattern == "*" || pattern == value))
+        .map(|fields| fields[4].clone());
+    Ok(password)
+}
@@ -0,0 +1,423 @@
+//! A connection checked out from the pool for exclusive use
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use deadpool_postgres::Object;
+use tokio_postgres::{Row, SimpleQueryMessage, Statement};
+
+#[cfg(feature = "arrow")]
+use crate::arrow::rows_to_pyarrow;
+use crate::cache::StatementCache;
+use crate::cursor::{Cursor, CursorTarget, Owner};
+use crate::locks::{self, AdvisoryLock, LockKey};
+use crate::error::{DbError, ErrorSql};
+use crate::explain::Explain;
+use crate::notices::NoticeQueue;
+use crate::paramstyle;
+use crate::simple;
+use crate::spans;
+use crate::{check_max_rows, count_query, fetch_backend_pid, query_counted, single_count};
+use crate::frames::rows_to_dataframe;
+use crate::tags::command_tag;
+use crate::timeouts::StatementTimeout;
+use crate::transaction::{self, Transaction};
+use crate::types::{DecodeOptions, PyValue, RowFactory, param_types, row_to_dict, column_keys, column_values, query_result, rows_to_columns, rows_to_list, ColumnRef};
+use crate::upsert;
+
+/// A single pooled connection, held until released
+/// Every statement runs on the same backend, so temp tables and session settings persist
+#[pyclass]
+pub struct PooledConnection {
+    client: Arc<Mutex<Option<Object>>>,
+    runtime: Arc<tokio::runtime::Runtime>,
+    statement_timeout: Duration,
+    decode: DecodeOptions,
+    statements: Arc<StatementCache>,
+    backend_pid: OnceLock<i32>,
+    notices: NoticeQueue,
+    error_sql: ErrorSql,
+}
+
+impl PooledConnection {
+    pub(crate) fn new(
+        client: Object,
+        runtime: Arc<tokio::runtime::Runtime>,
+        statement_timeout: Duration,
+        decode: DecodeOptions,
+        statements: Arc<StatementCache>,
+        notices: NoticeQueue,
+        error_sql: ErrorSql,
+    ) -> Self {
+        PooledConnection {
+            client: Arc::new(Mutex::new(Some(client))),
+            runtime,
+            statement_timeout,
+            decode,
+            statements,
+            backend_pid: OnceLock::new(),
+            notices,
+            error_sql,
+        }
+    }
+
+    /// Run a query, returning its statement alongside the rows and the affected row count
+    fn fetch(&self, sql: &str, params: Vec<PyValue>, timeout_secs: Option<u64>, max_rows: Option<usize>) -> PyResult<(Statement, Vec<Row>, u64)> {
+        paramstyle::check_param_count(sql, params.len())?;
+        let stmt_timeout = StatementTimeout::new(self.statement_timeout, timeout_secs);
+
+        let span = spans::query_span(sql, params.len());
+        let result = self.runtime.block_on(async {
+            let guard = self.client.lock().await;
+            let client = guard.as_ref().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+
+            let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync)).collect();
+
+            let result = stmt_timeout.run(client, async {
+                let statement = self.statements.prepare(&***client, sql, &param_types(&params)).await?;
+                let (rows, affected) = query_counted(client, &statement, &params_refs, max_rows).await?;
+                Ok((statement, rows, affected))
+            }).await
+                .map_err(|_| DbError::Timeout(format!("Query timed out after {}", stmt_timeout)))?
+                .map_err(DbError::Query)?;
+
+            Ok::<_, PyErr>(result)
+        });
+        spans::finish(&span, &result, |(_, rows, _)| rows.len() as u64);
+        self.notices.warn();
+        result.map_err(|e| self.error_sql.attach(e, sql))
+    }
+
+    /// Run `sql` over the simple query protocol, unprepared
+    fn fetch_simple(&self, sql: &str, timeout_secs: Option<u64>, max_rows: Option<usize>) -> PyResult<Vec<SimpleQueryMessage>> {
+        let stmt_timeout = StatementTimeout::new(self.statement_timeout, timeout_secs);
+
+        let span = spans::query_span(sql, 0);
+        let result = self.runtime.block_on(async {
+            let guard = self.client.lock().await;
+            let client = guard.as_ref().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+
+            let messages = stmt_timeout.run(client, simple::query(client, sql, max_rows)).await
+                .map_err(|_| DbError::Timeout(format!("Query timed out after {}", stmt_timeout)))?
+                .map_err(DbError::Query)?;
+
+            Ok::<_, PyErr>(messages)
+        });
+        spans::finish(&span, &result, |messages| simple::affected(messages));
+        self.notices.warn();
+        result.map_err(|e| self.error_sql.attach(e, sql))
+    }
+}
+
+#[pymethods]
+impl PooledConnection {
+    /// Execute a query and return rows as list of dicts
+    /// With `with_columns=True`, returns `(columns, rows)`: the column names in order, then the rows
+    #[pyo3(signature = (sql, params=None, timeout_secs=None, row_factory="dict", model=None, paramstyle=None, prepared=true, max_rows=None, with_columns=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn query<'py>(
+        &self,
+        py: Python<'py>,
+        sql: &str,
+        params: Option<Bound<'py, PyAny>>,
+        timeout_secs: Option<u64>,
+        row_factory: &str,
+        model: Option<Bound<'py, PyAny>>,
+        paramstyle: Option<&str>,
+        prepared: bool,
+        max_rows: Option<usize>,
+        with_columns: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let row_factory = RowFactory::new(row_factory, model)?;
+        let (sql, params) = paramstyle::bind(sql, params.as_ref(), paramstyle)?;
+        if !prepared {
+            simple::check_no_params(&params)?;
+            let messages = self.fetch_simple(&sql, timeout_secs, max_rows)?;
+            check_max_rows(simple::row_count(&messages), max_rows)?;
+            let rows = simple::rows_to_list(py, &messages, &row_factory)?;
+            return Ok(query_result(py, rows, with_columns.then(|| simple::column_keys(&messages))));
+        }
+        let (statement, rows, _) = self.fetch(&sql, params, timeout_secs, max_rows)?;
+        check_max_rows(rows.len(), max_rows)?;
+        let list = rows_to_list(py, &rows, &self.decode, &row_factory)?;
+        Ok(query_result(py, list, with_columns.then(|| column_keys(statement.columns()))))
+    }
+
+    /// Execute a query and return `{column: [values...]}` instead of per-row dicts
+    #[pyo3(signature = (sql, params=None, timeout_secs=None))]
+    fn query_columns<'py>(&self, py: Python<'py>, sql: &str, params: Option<Vec<PyValue>>, timeout_secs: Option<u64>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        let (statement, rows, _) = self.fetch(sql, params.unwrap_or_default(), timeout_secs, None)?;
+        rows_to_columns(py, statement.columns(), &rows, &self.decode)
+    }
+
+    /// Execute a query and return one column's values (by index or name) as a flat list
+    #[pyo3(signature = (sql, params=None, column=ColumnRef::Index(0), timeout_secs=None))]
+    fn fetch_column<'py>(
+        &self,
+        py: Python<'py>,
+        sql: &str,
+        params: Option<Vec<PyValue>>,
+        column: ColumnRef,
+        timeout_secs: Option<u64>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyList>> {
+        let (statement, rows, _) = self.fetch(sql, params.unwrap_or_default(), timeout_secs, None)?;
+        column_values(py, statement.columns(), &rows, &column, &self.decode)
+    }
+
+    /// Number of rows a query returns, counted on the server without fetching them
+    /// Runs `SELECT count(*) FROM (<sql>) sub`, so the query is executed in full
+    #[pyo3(signature = (sql, params=None, timeout_secs=None))]
+    fn row_count(&self, sql: &str, params: Option<Vec<PyValue>>, timeout_secs: Option<u64>) -> PyResult<i64> {
+        let (_, rows, _) = self.fetch(&count_query(sql), params.unwrap_or_default(), timeout_secs, None)?;
+        Ok(single_count(&rows))
+    }
+
+    /// The plan of a query from `EXPLAIN`: the parsed JSON document, or a list of text lines
+    /// With `analyze=True` the query really runs, changes included
+    #[pyo3(signature = (sql, params=None, analyze=false, format="json", buffers=false, verbose=false, timeout_secs=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn explain<'py>(
+        &self,
+        py: Python<'py>,
+        sql: &str,
+        params: Option<Vec<PyValue>>,
+        analyze: bool,
+        format: &str,
+        buffers: bool,
+        verbose: bool,
+        timeout_secs: Option<u64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let explain = Explain::new(format, analyze, verbose, buffers)?;
+        let (statement, rows, _) = self.fetch(&explain.statement(sql), params.unwrap_or_default(), timeout_secs, None)?;
+        explain.plan(py, &statement, &rows, &self.decode)
+    }
+
+    /// Execute a query and return a pandas DataFrame with per-column dtypes (requires pandas)
+    #[pyo3(signature = (sql, params=None, timeout_secs=None))]
+    fn query_df<'py>(&self, py: Python<'py>, sql: &str, params: Option<Vec<PyValue>>, timeout_secs: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        let (statement, rows, _) = self.fetch(sql, params.unwrap_or_default(), timeout_secs, None)?;
+        rows_to_dataframe(py, statement.columns(), &rows, &self.decode)
+    }
+
+    /// Execute a query and return a pyarrow.RecordBatch built without per-value Python objects
+    #[cfg(feature = "arrow")]
+    #[pyo3(signature = (sql, params=None, timeout_secs=None))]
+    fn query_arrow<'py>(&self, py: Python<'py>, sql: &str, params: Option<Vec<PyValue>>, timeout_secs: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        let (statement, rows, _) = self.fetch(sql, params.unwrap_or_default(), timeout_secs, None)?;
+        rows_to_pyarrow(py, statement.columns(), &rows)
+    }
+
+    /// Execute a statement and return the affected row count
+    #[pyo3(signature = (sql, params=None, timeout_secs=None, paramstyle=None, prepared=true))]
+    fn execute(
+        &self,
+        sql: &str,
+        params: Option<Bound<'_, PyAny>>,
+        timeout_secs: Option<u64>,
+        paramstyle: Option<&str>,
+        prepared: bool,
+    ) -> PyResult<u64> {
+        let (sql, params) = paramstyle::bind(sql, params.as_ref(), paramstyle)?;
+        if !prepared {
+            simple::check_no_params(&params)?;
+            return Ok(simple::affected(&self.fetch_simple(&sql, timeout_secs, None)?));
+        }
+        paramstyle::check_param_count(&sql, params.len())?;
+        let stmt_timeout = StatementTimeout::new(self.statement_timeout, timeout_secs);
+
+        let span = spans::query_span(&sql, params.len());
+        let result = self.runtime.block_on(async {
+            let guard = self.client.lock().await;
+            let client = guard.as_ref().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+
+            let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync)).collect();
+
+            let count = stmt_timeout.run(client, async {
+                let statement = self.statements.prepare(&***client, &sql, &param_types(&params)).await?;
+                client.execute(&statement, &params_refs).await
+            }).await
+                .map_err(|_| DbError::Timeout(format!("Execute timed out after {}", stmt_timeout)))?
+                .map_err(DbError::Query)?;
+
+            Ok::<_, PyErr>(count)
+        });
+        spans::finish(&span, &result, |count| *count);
+        self.notices.warn();
+        result.map_err(|e| self.error_sql.attach(e, &sql))
+    }
+
+    /// Execute a statement and return its command tag, e.g. "INSERT 0 5" or "CREATE TABLE"
+    /// The tag is rebuilt from the statement's leading keywords and row count, not read from the server
+    #[pyo3(signature = (sql, params=None, timeout_secs=None, paramstyle=None, prepared=true))]
+    fn execute_with_tag(
+        &self,
+        sql: &str,
+        params: Option<Bound<'_, PyAny>>,
+        timeout_secs: Option<u64>,
+        paramstyle: Option<&str>,
+        prepared: bool,
+    ) -> PyResult<String> {
+        let rows = self.execute(sql, params, timeout_secs, paramstyle, prepared)?;
+        Ok(command_tag(sql, rows))
+    }
+
+    /// Insert a row, or update the existing one when it conflicts on `conflict_columns`
+    /// `update_columns` defaults to every column outside the conflict target; the inserted
+    /// or updated row is returned as a dict in a list (empty when a conflict is left alone)
+    #[pyo3(signature = (table, columns, values, conflict_columns, update_columns=None, timeout_secs=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn upsert<'py>(
+        &self,
+        py: Python<'py>,
+        table: &str,
+        columns: Vec<String>,
+        values: Vec<PyValue>,
+        conflict_columns: Vec<String>,
+        update_columns: Option<Vec<String>>,
+        timeout_secs: Option<u64>,
+    ) -> PyResult<Bound<'py, pyo3::types::PyList>> {
+        upsert::check_row(&columns, values.len())?;
+        let sql = upsert::upsert_statement(table, &columns, &conflict_columns, update_columns.as_deref(), 1, true)?;
+        let (_, rows, _) = self.fetch(&sql, values, timeout_secs, None)?;
+        rows_to_list(py, &rows, &self.decode, &RowFactory::new("dict", None)?)
+    }
+
+    /// Execute multiple statements in a single transaction on this connection
+    /// Returns the affected row count of each statement
+    fn execute_many(&self, statements: Vec<(String, Option<Vec<PyValue>>)>) -> PyResult<Vec<u64>> {
+        let stmt_timeout = self.statement_timeout;
+        for (sql, params) in &statements {
+            paramstyle::check_param_count(sql, params.as_ref().map_or(0, Vec::len))?;
+        }
+
+        let result = self.runtime.block_on(async {
+            let mut guard = self.client.lock().await;
+            let client = guard.as_mut().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+            Ok(transaction::execute_many(client, &self.statements, statements, stmt_timeout, |_, _| {}).await?)
+        });
+        self.notices.warn();
+        result
+    }
+
+    /// Fetch a single row
+    #[pyo3(signature = (sql, params=None, timeout_secs=None))]
+    fn fetch_one<'py>(&self, py: Python<'py>, sql: &str, params: Option<Vec<PyValue>>, timeout_secs: Option<u64>) -> PyResult<Option<Bound<'py, pyo3::types::PyDict>>> {
+        let params = params.unwrap_or_default();
+        paramstyle::check_param_count(sql, params.len())?;
+        let stmt_timeout = StatementTimeout::new(self.statement_timeout, timeout_secs);
+
+        let span = spans::query_span(sql, params.len());
+        let row = self.runtime.block_on(async {
+            let guard = self.client.lock().await;
+            let client = guard.as_ref().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+
+            let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync)).collect();
+
+            let result = stmt_timeout.run(client, async {
+                let statement = self.statements.prepare(&***client, sql, &param_types(&params)).await?;
+                client.query_opt(&statement, &params_refs).await
+            }).await
+                .map_err(|_| DbError::Timeout(format!("Query timed out after {}", stmt_timeout)))?
+                .map_err(DbError::Query)?;
+
+            Ok::<_, PyErr>(result)
+        });
+        spans::finish(&span, &row, |row| row.is_some() as u64);
+        self.notices.warn();
+        let row = row.map_err(|e| self.error_sql.attach(e, sql))?;
+
+        match row {
+            Some(r) => Ok(Some(row_to_dict(py, &r, &self.decode)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Open a DB-API 2.0 cursor on this connection
+    fn cursor(slf: Py<Self>) -> Cursor {
+        Cursor::for_pooled(slf)
+    }
+
+    /// A transaction for a `with` block: committed at its end, rolled back if it raises
+    /// With `read_only=True` it begins as READ ONLY, so any write fails at the server
+    #[pyo3(signature = (read_only=false, isolation_level=None))]
+    fn transaction(slf: Py<Self>, read_only: bool, isolation_level: Option<&str>) -> PyResult<Transaction> {
+        Transaction::new(Owner::Pooled(slf), read_only, isolation_level)
+    }
+
+    /// Wait for the session-level advisory lock `key` (an int, or a pair of int32s)
+    /// Returns a guard; use it in a `with` block or call `release()`
+    fn advisory_lock(slf: Py<Self>, py: Python<'_>, key: LockKey) -> PyResult<AdvisoryLock> {
+        {
+            let connection = slf.borrow(py);
+            let connection = &*connection;
+            py.allow_threads(|| locks::call(connection, "pg_advisory_lock", key))?;
+        }
+        Ok(AdvisoryLock::new(Owner::Pooled(slf), key))
+    }
+
+    /// Take the advisory lock `key` if it's free; returns whether it was taken
+    fn try_advisory_lock(&self, key: LockKey) -> PyResult<bool> {
+        locks::call(self, "pg_try_advisory_lock", key)
+    }
+
+    /// Release one hold of the advisory lock `key`; returns False if this session didn't hold it
+    fn advisory_unlock(&self, key: LockKey) -> PyResult<bool> {
+        locks::call(self, "pg_advisory_unlock", key)
+    }
+
+    /// Process ID of the server backend handling this connection
+    fn backend_pid(&self) -> PyResult<i32> {
+        self.runtime.block_on(async {
+            let guard = self.client.lock().await;
+            let client = guard.as_ref().ok_or_else(|| PyRuntimeError::new_err("Connection released"))?;
+            if let Some(pid) = self.backend_pid.get() {
+                return Ok(*pid);
+            }
+            let pid = fetch_backend_pid(client).await.map_err(DbError::Query)?;
+            Ok(*self.backend_pid.get_or_init(|| pid))
+        })
+    }
+
+    /// Check if the connection has been returned to the pool
+    fn is_released(&self) -> bool {
+        self.runtime.block_on(async { self.client.lock().await.is_none() })
+    }
+
+    /// Return the connection to the pool
+    fn release(&self) {
+        self.runtime.block_on(async {
+            self.client.lock().await.take();
+        });
+    }
+
+    fn __repr__(&self) -> String {
+        match self.backend_pid.get() {
+            Some(pid) => format!("PooledConnection(released={}, pid={})", self.is_released(), pid),
+            None => format!("PooledConnection(released={})", self.is_released()),
+        }
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&self, _exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) {
+        self.release();
+    }
+}
+
+impl CursorTarget for PooledConnection {
+    fn run(&self, sql: &str, params: Vec<PyValue>) -> PyResult<(Statement, Vec<Row>, u64)> {
+        self.fetch(sql, params, None, None)
+    }
+
+    fn decode_options(&self) -> DecodeOptions {
+        self.decode
+    }
+}
@@ -0,0 +1,50 @@
+//! Quoting identifiers and literals for SQL that can't be parameterized
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyBool;
+
+/// Postgres rejects NUL bytes anywhere in a statement
+fn check_nul(text: &str, what: &str) -> PyResult<()> {
+    match text.contains('\0') {
+        true => Err(PyValueError::new_err(format!("{} cannot contain NUL characters", what))),
+        false => Ok(()),
+    }
+}
+
+/// Quote `name` as an identifier: wrapped in double quotes, embedded quotes doubled
+///
+/// The result always names exactly `name`, case and all; quote the parts of
+/// `schema.table` separately.
+#[pyfunction]
+pub fn quote_identifier(name: &str) -> PyResult<String> {
+    if name.is_empty() {
+        return Err(PyValueError::new_err("Identifier cannot be empty"));
+    }
+    check_nul(name, "Identifier")?;
+    Ok(format!("\"{}\"", name.replace('"', "\"\"")))
+}
+
+/// Quote a value as a string literal, like the server's quote_nullable()
+///
+/// None becomes NULL and booleans TRUE/FALSE; anything else is quoted as its
+/// str(). Single quotes are doubled, and a value with backslashes is written
+/// as an E'' string with the backslashes doubled, so the result is the same
+/// whatever standard_conforming_strings is set to.
+#[pyfunction]
+pub fn quote_literal(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if value.is_none() {
+        return Ok("NULL".to_string());
+    }
+    if let Ok(flag) = value.downcast::<PyBool>() {
+        return Ok(if flag.is_true() { "TRUE" } else { "FALSE" }.to_string());
+    }
+    let text = value.str()?.to_cow()?.into_owned();
+    check_nul(&text, "Literal")?;
+
+    let quoted = text.replace('\'', "''");
+    Ok(match quoted.contains('\\') {
+        true => format!("E'{}'", quoted.replace('\\', "\\\\")),
+        false => format!("'{}'", quoted),
+    })
+}
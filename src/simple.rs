@@ -0,0 +1,109 @@
+//! The simple query protocol, for poolers such as pgbouncer in transaction mode
+//!
+//! `simple_query` sends the SQL text as-is: nothing is prepared, so no
+//! statement outlives the transaction, but parameters can't be sent and every
+//! value comes back as text.
+
+use pyo3::prelude::*;
+use std::borrow::Cow;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyDict, PyList, PyTuple};
+use futures_util::TryStreamExt;
+use tokio_postgres::{Client, SimpleQueryMessage};
+
+use crate::types::{dict_to_model, namedtuple_class, unique_names, PyValue, RowFactory};
+
+/// Simple queries carry no parameters; values must be inlined with the quoting helpers
+pub fn check_no_params(params: &[PyValue]) -> PyResult<()> {
+    match params.is_empty() {
+        true => Ok(()),
+        false => Err(PyValueError::new_err(
+            "prepared=False sends SQL as-is and can't take parameters; inline values with quote_literal()",
+        )),
+    }
+}
+
+/// Run `sql` over the simple query protocol, collecting its messages
+///
+/// With `max_rows`, collection stops once a row past the limit arrives, like
+/// `query_counted`, so at most `max_rows + 1` rows are held.
+pub async fn query(client: &Client, sql: &str, max_rows: Option<usize>) -> Result<Vec<SimpleQueryMessage>, tokio_postgres::Error> {
+    let stream = client.simple_query_raw(sql).await?;
+    let mut stream = std::pin::pin!(stream);
+    let mut messages = Vec::new();
+    let mut rows = 0;
+    while let Some(message) = stream.try_next().await? {
+        if let SimpleQueryMessage::Row(_) = message {
+            rows += 1;
+        }
+        messages.push(message);
+        if max_rows.is_some_and(|max| rows > max) {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+/// Rows affected by all statements in a simple query
+pub fn affected(messages: &[SimpleQueryMessage]) -> u64 {
+    messages.iter()
+        .map(|message| match message {
+            SimpleQueryMessage::CommandComplete(rows) => *rows,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Rows returned by all statements in a simple query
+pub fn row_count(messages: &[SimpleQueryMessage]) -> usize {
+    messages.iter().filter(|message| matches!(message, SimpleQueryMessage::Row(_))).count()
+}
+
+/// Column names of the first result in a simple query, deduplicated like dict row keys
+pub fn column_keys(messages: &[SimpleQueryMessage]) -> Vec<Cow<'_, str>> {
+    messages.iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::RowDescription(columns) => Some(unique_names(&columns.iter().map(|c| c.name()).collect::<Vec<_>>())),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Convert the rows of a simple query to a Python list, building each row with `factory`
+///
+/// Values are the server's text output (or None for NULL). Rows of every
+/// statement in the query are returned, in order.
+pub fn rows_to_list<'py>(py: Python<'py>, messages: &[SimpleQueryMessage], factory: &RowFactory<'py>) -> PyResult<Bound<'py, PyList>> {
+    let result = PyList::empty_bound(py);
+    let mut row_class = None;
+    for message in messages {
+        let row = match message {
+            SimpleQueryMessage::RowDescription(columns) => {
+                if let RowFactory::NamedTuple = factory {
+                    row_class = Some(namedtuple_class(py, columns.iter().map(|c| c.name()))?);
+                }
+                continue;
+            }
+            SimpleQueryMessage::Row(row) => row,
+            _ => continue,
+        };
+
+        let values = (0..row.len()).map(|i| row.get(i)).collect::<Vec<_>>();
+        match (factory, &row_class) {
+            (RowFactory::Dict | RowFactory::Model(_), _) => {
+                let names = row.columns().iter().map(|c| c.name()).collect::<Vec<_>>();
+                let dict = PyDict::new_bound(py);
+                for (key, value) in unique_names(&names).iter().zip(values) {
+                    dict.set_item(key.as_ref(), value)?;
+                }
+                match factory {
+                    RowFactory::Model(model) => result.append(dict_to_model(py, &dict, model, result.len())?)?,
+                    _ => result.append(dict)?,
+                }
+            }
+            (_, Some(class)) => result.append(class.call1(PyTuple::new_bound(py, values))?)?,
+            (_, None) => result.append(PyTuple::new_bound(py, values))?,
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,62 @@
+//! Reporting queries slower than `slow_query_threshold_ms` to a Python callback
+
+use pyo3::prelude::*;
+use parking_lot::{Mutex, RwLock};
+use std::time::Duration;
+
+/// A pool's slow query threshold and callback
+///
+/// Slow queries are queued while the runtime is busy and reported by
+/// `flush()` once it returns, so the callback can itself use the pool.
+pub(crate) struct SlowQueryLog {
+    /// Zero disables the log
+    threshold: Duration,
+    callback: RwLock<Option<PyObject>>,
+    pending: Mutex<Vec<(String, f64)>>,
+}
+
+impl SlowQueryLog {
+    pub fn new(threshold_ms: u64) -> Self {
+        SlowQueryLog {
+            threshold: Duration::from_millis(threshold_ms),
+            callback: RwLock::new(None),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn set_callback(&self, callback: Option<PyObject>) {
+        *self.callback.write() = callback;
+    }
+
+    fn enabled(&self) -> bool {
+        !self.threshold.is_zero() && self.callback.read().is_some()
+    }
+
+    /// Queue `sql` for the callback if it took longer than the threshold
+    pub fn record(&self, sql: &str, elapsed: Duration) {
+        if self.enabled() && elapsed > self.threshold {
+            self.pending.lock().push((sql.to_string(), elapsed.as_secs_f64() * 1000.0));
+        }
+    }
+
+    /// Call the callback with `(sql, duration_ms)` for each queued slow query
+    ///
+    /// A callback that raises is reported as unraisable rather than failing the query.
+    pub fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        if pending.is_empty() {
+            return;
+        }
+        Python::with_gil(|py| {
+            let Some(callback) = self.callback.read().as_ref().map(|c| c.clone_ref(py)) else {
+                return;
+            };
+            let callback = callback.bind(py);
+            for (sql, duration_ms) in pending {
+                if let Err(err) = callback.call1((sql, duration_ms)) {
+                    err.write_unraisable_bound(py, Some(callback));
+                }
+            }
+        });
+    }
+}
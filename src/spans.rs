@@ -0,0 +1,248 @@
+//! `tracing` spans for statements, and a subscriber forwarding them to Python
+//!
+//! Each query, execute and fetch_one opens a `db.query` span. Nothing is
+//! recorded until `init_tracing(callback)` installs the subscriber, which calls
+//! `callback(name, attributes, start_ns, end_ns)` as each span closes, on the
+//! thread that ran the statement, so Python tracers can parent it to their
+//! current span.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError};
+use pyo3::types::PyDict;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Once;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Span, Subscriber};
+
+use crate::tags::command_name;
+
+static CALLBACK: RwLock<Option<PyObject>> = RwLock::new(None);
+static INSTALL: Once = Once::new();
+/// Longest statement text recorded, in characters (0 records it whole)
+static MAX_SQL_LENGTH: AtomicUsize = AtomicUsize::new(1024);
+static INCLUDE_SQL: AtomicBool = AtomicBool::new(true);
+
+/// Forward closed `db_connector` spans to `callback(name, attributes, start_ns, end_ns)`
+///
+/// `attributes` holds `db.system`, `db.operation` (the SQL command, e.g.
+/// SELECT), `db.statement` (cut to `max_sql_length` characters, or left out
+/// with `include_sql=False`), `db.params`, `db.rows` and, for a failed
+/// statement, `error`. Times are nanoseconds since the epoch. Calling it again
+/// replaces the callback; None stops tracing.
+#[pyfunction]
+#[pyo3(signature = (callback, max_sql_length=1024, include_sql=true))]
+pub fn init_tracing(callback: Option<&Bound<'_, PyAny>>, max_sql_length: usize, include_sql: bool) -> PyResult<()> {
+    if let Some(callback) = callback {
+        if !callback.is_callable() {
+            return Err(PyTypeError::new_err(format!("{} is not callable", callback)));
+        }
+    }
+    let mut installed = Ok(());
+    INSTALL.call_once(|| {
+        installed = tracing::subscriber::set_global_default(PySubscriber::default())
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to install the tracing subscriber: {}", e)));
+    });
+    installed?;
+
+    MAX_SQL_LENGTH.store(max_sql_length, Ordering::Relaxed);
+    INCLUDE_SQL.store(include_sql, Ordering::Relaxed);
+    *CALLBACK.write() = callback.map(|c| c.clone().unbind());
+    Ok(())
+}
+
+/// Statement text cut to `MAX_SQL_LENGTH` characters
+struct Statement<'a>(&'a str);
+
+impl fmt::Display for Statement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match MAX_SQL_LENGTH.load(Ordering::Relaxed) {
+            0 => f.write_str(self.0),
+            limit => match self.0.char_indices().nth(limit) {
+                Some((end, _)) => write!(f, "{}...", &self.0[..end]),
+                None => f.write_str(self.0),
+            },
+        }
+    }
+}
+
+/// Open the span for one statement; `finish` records how it went
+pub(crate) fn query_span(sql: &str, params: usize) -> Span {
+    let span = tracing::info_span!(
+        "db.query",
+        db.system = "postgresql",
+        db.operation = command_name(sql).as_deref().unwrap_or(""),
+        db.statement = tracing::field::Empty,
+        db.params = params,
+        db.rows = tracing::field::Empty,
+        error = tracing::field::Empty,
+    );
+    if INCLUDE_SQL.load(Ordering::Relaxed) {
+        span.record("db.statement", tracing::field::display(Statement(sql)));
+    }
+    span
+}
+
+/// Record the rows returned or affected by a statement, or its error
+pub(crate) fn finish<T, E: fmt::Display>(span: &Span, result: &Result<T, E>, rows: impl FnOnce(&T) -> u64) {
+    if span.is_disabled() {
+        return;
+    }
+    match result {
+        Ok(value) => span.record("db.rows", rows(value)),
+        Err(err) => span.record("error", tracing::field::display(err)),
+    };
+}
+
+enum FieldValue {
+    Str(String),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+}
+
+struct SpanData {
+    name: &'static str,
+    fields: Vec<(&'static str, FieldValue)>,
+    start: Instant,
+    start_ns: u128,
+    refs: usize,
+}
+
+impl Visit for SpanData {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.push((field.name(), FieldValue::Str(format!("{:?}", value))));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.push((field.name(), FieldValue::Str(value.to_string())));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name(), FieldValue::Int(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.push((field.name(), FieldValue::UInt(value)));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.push((field.name(), FieldValue::Float(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.push((field.name(), FieldValue::Bool(value)));
+    }
+}
+
+/// Keeps open spans until they close, then hands them to the Python callback
+#[derive(Default)]
+struct PySubscriber {
+    spans: Mutex<HashMap<u64, SpanData>>,
+    next_id: AtomicU64,
+}
+
+impl PySubscriber {
+    fn report(span: SpanData) {
+        let end_ns = span.start_ns + span.start.elapsed().as_nanos();
+        Python::with_gil(|py| {
+            let Some(callback) = CALLBACK.read().as_ref().map(|c| c.clone_ref(py)) else {
+                return;
+            };
+            let callback = callback.bind(py);
+            let attributes = PyDict::new_bound(py);
+            let reported = (|| {
+                for (name, value) in span.fields {
+                    match value {
+                        FieldValue::Str(v) => attributes.set_item(name, v)?,
+                        FieldValue::Int(v) => attributes.set_item(name, v)?,
+                        FieldValue::UInt(v) => attributes.set_item(name, v)?,
+                        FieldValue::Float(v) => attributes.set_item(name, v)?,
+                        FieldValue::Bool(v) => attributes.set_item(name, v)?,
+                    }
+                }
+                callback.call1((span.name, attributes, span.start_ns, end_ns)).map(drop)
+            })();
+            // A broken callback mustn't fail the statement it traces
+            if let Err(err) = reported {
+                err.write_unraisable_bound(py, Some(callback));
+            }
+        });
+    }
+}
+
+impl Subscriber for PySubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        match metadata.is_span() && metadata.target().starts_with("db_connector") {
+            true => Interest::sometimes(),
+            false => Interest::never(),
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span() && metadata.target().starts_with("db_connector") && CALLBACK.read().is_some()
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut data = SpanData {
+            name: span.metadata().name(),
+            fields: Vec::new(),
+            start: Instant::now(),
+            start_ns: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos()),
+            refs: 1,
+        };
+        span.record(&mut data);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.spans.lock().insert(id, data);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(data) = self.spans.lock().get_mut(&span.into_u64()) {
+            values.record(data);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(data) = self.spans.lock().get_mut(&id.into_u64()) {
+            data.refs += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let closed = {
+            let mut spans = self.spans.lock();
+            match spans.get_mut(&id.into_u64()) {
+                Some(data) if data.refs > 1 => {
+                    data.refs -= 1;
+                    None
+                }
+                Some(_) => spans.remove(&id.into_u64()),
+                None => None,
+            }
+        };
+        match closed {
+            // Reported outside the lock, as the callback may run statements of its own
+            Some(data) => {
+                Self::report(data);
+                true
+            }
+            None => false,
+        }
+    }
+}
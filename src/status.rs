@@ -0,0 +1,70 @@
+//! Server `ParameterStatus` values (`server_encoding`, `TimeZone`, ...)
+//!
+//! tokio-postgres keeps the values the server reports on its `Connection`,
+//! which is moved into the task driving it. That task copies them here after
+//! each poll, so they can be read without a round trip.
+//!
+//! The values stay locked while the task polls: a statement's response is
+//! delivered during the poll that also reads the parameters it changed, so a
+//! caller woken by it waits for those to be copied rather than seeing old ones.
+
+use parking_lot::{Mutex, MutexGuard};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Parameters the server reports at startup and again whenever they change
+const REPORTED: &[&str] = &[
+    "application_name",
+    "client_encoding",
+    "DateStyle",
+    "default_transaction_read_only",
+    "in_hot_standby",
+    "integer_datetimes",
+    "IntervalStyle",
+    "is_superuser",
+    "scram_iterations",
+    "server_encoding",
+    "server_version",
+    "session_authorization",
+    "standard_conforming_strings",
+    "TimeZone",
+];
+
+/// The latest reported parameter values of one connection
+#[derive(Clone, Default)]
+pub(crate) struct ParameterStatus(Arc<Mutex<HashMap<&'static str, String>>>);
+
+impl ParameterStatus {
+    /// Hold the values, e.g. across a poll of the connection, until `refresh`
+    pub fn lock(&self) -> Values<'_> {
+        Values(self.0.lock())
+    }
+
+    /// The value of parameter `name`, matched case-insensitively like a GUC name
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.lock().iter()
+            .find(|(reported, _)| reported.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// The locked values of a `ParameterStatus`
+pub(crate) struct Values<'a>(MutexGuard<'a, HashMap<&'static str, String>>);
+
+impl Values<'_> {
+    /// Copy the reported parameters from `lookup`, the connection's own cache
+    pub fn refresh<'a>(&mut self, lookup: impl Fn(&str) -> Option<&'a str>) {
+        let values = &mut self.0;
+        for &name in REPORTED {
+            match lookup(name) {
+                Some(value) if values.get(name).map(String::as_str) != Some(value) => {
+                    values.insert(name, value.to_string());
+                }
+                Some(_) => {}
+                None => {
+                    values.remove(name);
+                }
+            }
+        }
+    }
+}
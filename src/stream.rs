@@ -0,0 +1,168 @@
+//! Streaming large results through a server-side cursor
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyDict;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use deadpool_postgres::Object;
+use tokio_postgres::Row;
+
+use crate::error::DbError;
+use crate::types::{param_types, row_to_dict, DecodeOptions, PyValue};
+
+/// Numbers server-side cursors so their names are unique on a connection
+static CURSOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Lazily fetched rows of a query, `fetch_size` per round trip
+///
+/// Holds a pool connection with an open transaction until the rows run out,
+/// `close()` is called or the iterator is garbage-collected; the connection is
+/// then returned to the pool.
+#[pyclass]
+pub struct RowIterator {
+    client: Option<Object>,
+    runtime: Arc<tokio::runtime::Runtime>,
+    cursor: String,
+    fetch_size: usize,
+    buffer: VecDeque<Row>,
+    exhausted: bool,
+    decode: DecodeOptions,
+}
+
+impl RowIterator {
+    /// Declare a cursor for `sql` on `client` inside a new transaction
+    pub(crate) fn open(
+        client: Object,
+        runtime: Arc<tokio::runtime::Runtime>,
+        sql: &str,
+        params: Vec<PyValue>,
+        fetch_size: usize,
+        decode: DecodeOptions,
+    ) -> Result<Self, DbError> {
+        let cursor = format!("db_connector_rows_{}", CURSOR_ID.fetch_add(1, Ordering::Relaxed));
+        let declare = format!("DECLARE {} NO SCROLL CURSOR FOR {}", cursor, sql);
+
+        runtime.block_on(async {
+            let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync)).collect();
+
+            client.batch_execute("BEGIN").await?;
+            let declared = async {
+                let statement = client.prepare_typed(&declare, &param_types(&params)).await?;
+                client.execute(&statement, &params_refs).await
+            }.await;
+            if let Err(e) = declared {
+                // Leave no failed transaction behind on the pooled connection
+                let _ = client.batch_execute("ROLLBACK").await;
+                return Err(e);
+            }
+            Ok(())
+        })?;
+
+        Ok(RowIterator {
+            client: Some(client),
+            runtime,
+            cursor,
+            fetch_size,
+            buffer: VecDeque::new(),
+            exhausted: false,
+            decode,
+        })
+    }
+
+    /// Refill the buffer with the next batch; the last, short batch ends the iteration
+    fn fill(&mut self) -> PyResult<()> {
+        let Some(client) = self.client.as_ref() else {
+            return Ok(());
+        };
+        let fetch = format!("FETCH FORWARD {} FROM {}", self.fetch_size, self.cursor);
+        let rows = match self.runtime.block_on(client.query(&fetch, &[])) {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.close();
+                return Err(DbError::Query(e).into());
+            }
+        };
+        self.exhausted = rows.len() < self.fetch_size;
+        self.buffer.extend(rows);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl RowIterator {
+    fn __iter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        if self.buffer.is_empty() && !self.exhausted {
+            self.fill()?;
+        }
+        match self.buffer.pop_front() {
+            Some(row) => Ok(Some(row_to_dict(py, &row, &self.decode)?)),
+            None => {
+                self.close();
+                Ok(None)
+            }
+        }
+    }
+
+    /// Stop iterating and return the connection to the pool
+    fn close(&mut self) {
+        self.buffer.clear();
+        self.exhausted = true;
+        if let Some(client) = self.client.take() {
+            // Ending the transaction closes the cursor; the connection goes back to the pool when dropped,
+            // unless it can't be rolled back, in which case it's discarded
+            if self.runtime.block_on(client.batch_execute("ROLLBACK")).is_err() {
+                drop(Object::take(client));
+            }
+        }
+    }
+
+    /// Rows fetched per round trip; a new size applies from the next fetch
+    #[getter]
+    fn fetch_size(&self) -> usize {
+        self.fetch_size
+    }
+
+    #[setter]
+    fn set_fetch_size(&mut self, fetch_size: usize) -> PyResult<()> {
+        if fetch_size == 0 {
+            return Err(PyValueError::new_err("fetch_size must be at least 1"));
+        }
+        self.fetch_size = fetch_size;
+        Ok(())
+    }
+
+    /// Whether the connection has been returned to the pool
+    #[getter]
+    fn closed(&self) -> bool {
+        self.client.is_none()
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&mut self, _exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) {
+        self.close();
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RowIterator(fetch_size={}, closed={})", self.fetch_size, self.closed())
+    }
+}
+
+impl Drop for RowIterator {
+    fn drop(&mut self) {
+        if self.client.is_some() {
+            self.close();
+        }
+    }
+}
+
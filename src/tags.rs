@@ -0,0 +1,194 @@
+//! Command tags ("INSERT 0 5", "CREATE TABLE") for executed statements
+//!
+//! tokio-postgres keeps only the row count from the server's CommandComplete
+//! message, so the tag is rebuilt from the statement's leading keywords,
+//! naming commands the way the server does.
+
+/// Commands whose tag carries a row count
+const COUNTED: &[&str] = &["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "MOVE", "FETCH", "COPY"];
+
+/// Words between CREATE and the object type that the server leaves out of the tag
+const CREATE_MODIFIERS: &[&str] = &[
+    "OR", "REPLACE", "UNIQUE", "TEMP", "TEMPORARY", "UNLOGGED", "GLOBAL", "LOCAL", "RECURSIVE", "TRUSTED",
+    "PROCEDURAL", "DEFAULT", "CONSTRAINT",
+];
+
+/// Upper-cased words outside parentheses, string literals (dollar-quoted ones too),
+/// quoted identifiers and comments
+/// A quoted identifier becomes an empty word so it can't be mistaken for a keyword.
+fn top_level_words(sql: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '\'' => {
+                // '' inside a literal is an escaped quote, which this skips as two literals
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                }
+            }
+            '$' => {
+                // $tag$ opens a dollar-quoted string, while $1 is a parameter
+                let mut tag = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    tag.push(next);
+                    chars.next();
+                }
+                if chars.peek() == Some(&'$') && !tag.starts_with(|c: char| c.is_ascii_digit()) {
+                    chars.next();
+                    let delimiter = format!("${}$", tag);
+                    let mut body = String::new();
+                    for c in chars.by_ref() {
+                        body.push(c);
+                        if body.ends_with(&delimiter) {
+                            break;
+                        }
+                    }
+                }
+            }
+            '"' => {
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                }
+                if depth == 0 {
+                    words.push(String::new());
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_uppercase().collect::<String>();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_' || next == '$') {
+                        break;
+                    }
+                    word.extend(next.to_uppercase());
+                    chars.next();
+                }
+                if depth == 0 {
+                    words.push(word);
+                }
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// The command a statement runs, as its tag names it
+fn command_verb(words: &[String]) -> &str {
+    match words.first().map(String::as_str).unwrap_or("") {
+        // The statement after the CTEs names the command
+        "WITH" => words.iter().map(String::as_str).find(|w| COUNTED.contains(w)).unwrap_or("SELECT"),
+        "VALUES" | "TABLE" => "SELECT",
+        other => other,
+    }
+}
+
+/// The command a statement runs, e.g. "SELECT" for `WITH ... SELECT`, if it has one
+pub fn command_name(sql: &str) -> Option<String> {
+    let words = top_level_words(sql);
+    Some(command_verb(&words).to_string()).filter(|verb| !verb.is_empty())
+}
+
+/// Whether `sql` opens (`Some(true)`) or ends (`Some(false)`) a transaction block,
+/// judged by its leading keywords like its tag is
+pub fn transaction_change(sql: &str) -> Option<bool> {
+    let words = top_level_words(sql);
+    let has = |word: &str| words.iter().any(|w| w == word);
+    match words.first().map(String::as_str).unwrap_or("") {
+        "BEGIN" | "START" => Some(true),
+        // Rolling back to a savepoint keeps the transaction going
+        "ROLLBACK" | "ABORT" if has("TO") => None,
+        // ... AND CHAIN starts the next transaction right away
+        "COMMIT" | "END" | "ROLLBACK" | "ABORT" => Some(has("CHAIN") && !has("NO")),
+        "PREPARE" if has("TRANSACTION") => Some(false),
+        _ => None,
+    }
+}
+
+/// Tag for CREATE, ALTER and DROP: the verb and the object type, e.g. "CREATE INDEX"
+fn object_tag(verb: &str, words: &[String], rows: u64) -> String {
+    let mut words: Vec<&str> = words.iter().map(String::as_str).collect();
+    if verb == "CREATE" {
+        let modifiers = words.iter().take_while(|w| CREATE_MODIFIERS.contains(w)).count();
+        words.drain(..modifiers);
+    }
+    let word = |i: usize| words.get(i).copied().unwrap_or("");
+
+    // CREATE TABLE ... AS and CREATE MATERIALIZED VIEW report the rows they stored
+    let is_table_as = word(0) == "TABLE" && words.contains(&"AS");
+    if verb == "CREATE" && (is_table_as || (word(0), word(1)) == ("MATERIALIZED", "VIEW")) {
+        return format!("SELECT {}", rows);
+    }
+
+    let object = match (word(0), word(1)) {
+        ("FOREIGN", "DATA") | ("TEXT", "SEARCH") => words.iter().take(3).copied().collect::<Vec<_>>().join(" "),
+        ("FOREIGN", "TABLE") | ("MATERIALIZED", "VIEW") | ("EVENT", "TRIGGER") | ("ACCESS", "METHOD")
+        | ("USER", "MAPPING") | ("OPERATOR", "CLASS") | ("OPERATOR", "FAMILY") | ("DEFAULT", "PRIVILEGES") => {
+            format!("{} {}", word(0), word(1))
+        }
+        // Users and groups are roles to the server
+        ("USER", _) | ("GROUP", _) => "ROLE".to_string(),
+        (object, _) => object.to_string(),
+    };
+    match object.is_empty() {
+        true => verb.to_string(),
+        false => format!("{} {}", verb, object),
+    }
+}
+
+/// The command tag for `sql` after it affected `rows` rows
+///
+/// This is rebuilt from the statement's words, not read from the server, so it
+/// matches the server's tag for the commands above and repeats the leading
+/// keyword for any other.
+pub fn command_tag(sql: &str, rows: u64) -> String {
+    let words = top_level_words(sql);
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or("");
+    let verb = command_verb(&words);
+
+    match verb {
+        "INSERT" => format!("INSERT 0 {}", rows),
+        verb if COUNTED.contains(&verb) => format!("{} {}", verb, rows),
+        "CREATE" | "ALTER" | "DROP" => object_tag(verb, &words[1..], rows),
+        "START" => "START TRANSACTION".to_string(),
+        "END" => "COMMIT".to_string(),
+        "ABORT" => "ROLLBACK".to_string(),
+        "COMMIT" | "ROLLBACK" if word(1) == "PREPARED" => format!("{} PREPARED", verb),
+        "PREPARE" if word(1) == "TRANSACTION" => "PREPARE TRANSACTION".to_string(),
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "LOCK" => "LOCK TABLE".to_string(),
+        "REFRESH" => "REFRESH MATERIALIZED VIEW".to_string(),
+        "DECLARE" => "DECLARE CURSOR".to_string(),
+        "CLOSE" if word(1) == "ALL" => "CLOSE CURSOR ALL".to_string(),
+        "CLOSE" => "CLOSE CURSOR".to_string(),
+        "DISCARD" => format!("DISCARD {}", if word(1) == "TEMPORARY" { "TEMP" } else { word(1) }),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,67 @@
+//! Per-call statement timeouts
+
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::error::Elapsed;
+use tokio_postgres::{Client, Error};
+
+/// The timeout applied to a single statement
+///
+/// Calls without a `timeout_secs` override use the configured default, which the
+/// server also enforces through the connection's startup options when
+/// `server_statement_timeout` is on. An override is also applied on the server
+/// for the duration of the call.
+#[derive(Clone, Copy, Debug)]
+pub struct StatementTimeout {
+    limit: Option<Duration>,
+    overridden: bool,
+}
+
+impl StatementTimeout {
+    /// Resolve a call's `timeout_secs` against the configured default (0 means no timeout)
+    pub fn new(default: Duration, timeout_secs: Option<u64>) -> Self {
+        let limit = timeout_secs.map_or(default, Duration::from_secs);
+        StatementTimeout {
+            limit: (!limit.is_zero()).then_some(limit),
+            overridden: timeout_secs.is_some(),
+        }
+    }
+
+    /// Run `future` on `client` under this timeout
+    pub async fn run<F, T>(self, client: &Client, future: F) -> Result<Result<T, Error>, Elapsed>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        if !self.overridden {
+            return self.guard(future).await;
+        }
+
+        let millis = self.limit.map_or(0, |limit| limit.as_millis());
+        if let Err(e) = client.batch_execute(&format!("SET statement_timeout = {}", millis)).await {
+            return Ok(Err(e));
+        }
+        let result = self.guard(future).await;
+        // Restore the connection's default even if the statement failed or timed out
+        match (result, client.batch_execute("RESET statement_timeout").await) {
+            (Ok(Ok(_)), Err(e)) => Ok(Err(e)),
+            (result, _) => result,
+        }
+    }
+
+    async fn guard<F: Future>(self, future: F) -> Result<F::Output, Elapsed> {
+        match self.limit {
+            Some(limit) => tokio::time::timeout(limit, future).await,
+            None => Ok(future.await),
+        }
+    }
+}
+
+impl fmt::Display for StatementTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Some(limit) => write!(f, "{:?}", limit),
+            None => write!(f, "no limit"),
+        }
+    }
+}
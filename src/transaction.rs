@@ -0,0 +1,189 @@
+//! Explicit transactions (`with conn.transaction():`) on a `Connection` or a
+//! `PooledConnection` from `acquire()`
+//!
+//! Pool methods each check out their own connection, so a transaction spanning
+//! several calls needs a connection of its own; `AsyncPool.transaction(body)`
+//! acquires one and runs `body` in a transaction on it, retrying on conflicts.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use deadpool_postgres::Object;
+
+use crate::cache::StatementCache;
+use crate::cursor::{CursorTarget, Owner};
+use crate::error::DbError;
+use crate::pooled::PooledConnection;
+use crate::types::{param_types, PyValue};
+
+/// SQLSTATEs after which a transaction can simply be run again: serialization failure and deadlock
+const RETRYABLE: [&str; 2] = ["40001", "40P01"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    NotStarted,
+    Open,
+    Finished,
+}
+
+/// A transaction begun by entering a `with` block, committed when the block
+/// ends normally and rolled back when it raises
+#[pyclass]
+pub struct Transaction {
+    owner: Owner,
+    /// Begun as READ ONLY, so any write fails at the server
+    #[pyo3(get)]
+    read_only: bool,
+    /// "read committed", "repeatable read" or "serializable"; None keeps the session default
+    #[pyo3(get)]
+    isolation_level: Option<String>,
+    state: State,
+}
+
+impl Transaction {
+    pub(crate) fn new(owner: Owner, read_only: bool, isolation_level: Option<&str>) -> PyResult<Self> {
+        let isolation_level = isolation_level
+            .map(|level| match level.to_ascii_lowercase().replace('_', " ").as_str() {
+                level @ ("read committed" | "repeatable read" | "serializable") => Ok(level.to_string()),
+                _ => Err(PyValueError::new_err(format!(
+                    "Invalid isolation_level '{}'. Use 'read committed', 'repeatable read' or 'serializable'",
+                    level
+                ))),
+            })
+            .transpose()?;
+        Ok(Transaction { owner, read_only, isolation_level, state: State::NotStarted })
+    }
+
+    fn begin(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self.state != State::NotStarted {
+            return Err(PyRuntimeError::new_err("Transaction already started; call transaction() for a new one"));
+        }
+        let mut begin = "BEGIN".to_string();
+        if let Some(level) = &self.isolation_level {
+            begin.push_str(" ISOLATION LEVEL ");
+            begin.push_str(&level.to_ascii_uppercase());
+        }
+        if self.read_only {
+            begin.push_str(" READ ONLY");
+        }
+        self.run(py, &begin)?;
+        self.state = State::Open;
+        Ok(())
+    }
+
+    /// Commit or roll back; a failed COMMIT also ends the transaction on the server
+    fn end(&mut self, py: Python<'_>, commit: bool) -> PyResult<()> {
+        if self.state != State::Open {
+            return Ok(());
+        }
+        self.state = State::Finished;
+        self.run(py, if commit { "COMMIT" } else { "ROLLBACK" })
+    }
+
+    fn run(&self, py: Python<'_>, sql: &str) -> PyResult<()> {
+        match &self.owner {
+            Owner::Connection(connection) => connection.bind(py).borrow().run(sql, Vec::new()).map(drop),
+            Owner::Pooled(connection) => connection.bind(py).borrow().run(sql, Vec::new()).map(drop),
+        }
+    }
+}
+
+#[pymethods]
+impl Transaction {
+    fn __enter__(slf: Bound<'_, Self>) -> PyResult<Bound<'_, Self>> {
+        slf.borrow_mut().begin(slf.py())?;
+        Ok(slf)
+    }
+
+    /// Commit, or roll back if the block raised; the exception still propagates
+    #[pyo3(signature = (exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(&mut self, py: Python<'_>, exc_type: Option<PyObject>, _exc_val: Option<PyObject>, _exc_tb: Option<PyObject>) -> PyResult<bool> {
+        self.end(py, exc_type.is_none())?;
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        let state = match self.state {
+            State::NotStarted => "not started",
+            State::Open => "open",
+            State::Finished => "finished",
+        };
+        format!("Transaction(read_only={}, state='{}')", if self.read_only { "True" } else { "False" }, state)
+    }
+}
+
+/// Whether `err` is a serialization failure or deadlock, after which the transaction can be re-run
+fn is_retryable(py: Python<'_>, err: &PyErr) -> bool {
+    err.value_bound(py)
+        .getattr("sqlstate")
+        .and_then(|sqlstate| sqlstate.extract::<String>())
+        .is_ok_and(|sqlstate| RETRYABLE.contains(&sqlstate.as_str()))
+}
+
+/// Run `body(connection)` in a transaction and commit, re-running the whole
+/// transaction up to `retries` more times when it fails with a retryable error
+///
+/// The wait between attempts starts at `backoff` and doubles each time.
+pub(crate) fn run_with_retry(
+    py: Python<'_>,
+    connection: &Py<PooledConnection>,
+    body: &Bound<'_, PyAny>,
+    retries: u32,
+    transaction: impl Fn() -> PyResult<Transaction>,
+    backoff: Duration,
+) -> PyResult<PyObject> {
+    let mut delay = backoff;
+    let mut attempt = 0;
+    loop {
+        let mut tx = transaction()?;
+        tx.begin(py)?;
+        let err = match body.call1((connection.clone_ref(py),)).and_then(|value| tx.end(py, true).map(|_| value)) {
+            Ok(value) => return Ok(value.unbind()),
+            Err(err) => err,
+        };
+        // The body's error is the one worth reporting, not a failed rollback
+        let _ = tx.end(py, false);
+        if attempt >= retries || !is_retryable(py, &err) {
+            return Err(err);
+        }
+        attempt += 1;
+        py.allow_threads(|| std::thread::sleep(delay));
+        delay *= 2;
+    }
+}
+
+/// Run `statements` in one transaction on `client` and commit, returning each one's row count
+///
+/// Each statement gets `stmt_timeout` of its own, and `executed` is told how long
+/// it took, e.g. for a pool's metrics. An error rolls the whole batch back.
+pub(crate) async fn execute_many(
+    client: &mut Object,
+    cache: &StatementCache,
+    statements: Vec<(String, Option<Vec<PyValue>>)>,
+    stmt_timeout: Duration,
+    executed: impl Fn(&str, Duration),
+) -> Result<Vec<u64>, DbError> {
+    let transaction = client.transaction().await.map_err(DbError::Query)?;
+
+    let mut counts = Vec::new();
+    for (sql, params) in statements {
+        let params = params.unwrap_or_default();
+        let params_refs: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
+            params.iter().map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync)).collect();
+
+        let count = timeout(stmt_timeout, async {
+            let statement = cache.prepare(&*transaction, &sql, &param_types(&params)).await?;
+            let start = Instant::now();
+            let count = transaction.execute(&statement, &params_refs).await;
+            executed(&sql, start.elapsed());
+            count
+        }).await
+            .map_err(|_| DbError::Timeout(format!("Transaction statement timed out after {:?}", stmt_timeout)))?
+            .map_err(DbError::Query)?;
+        counts.push(count);
+    }
+
+    transaction.commit().await.map_err(DbError::Query)?;
+    Ok(counts)
+}
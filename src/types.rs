@@ -1,13 +1,93 @@
 //! Type conversions between Rust, PostgreSQL, and Python
 
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-use pyo3::exceptions::PyTypeError;
-use tokio_postgres::Row;
-use tokio_postgres::types::{ToSql, Type};
+use std::borrow::Cow;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDate, PyDateAccess, PyDateTime, PyDelta, PyDeltaAccess, PyDict, PyFloat, PyInt,
+    PyList, PyMemoryView, PyString, PyTime, PyTimeAccess, PyTuple, PyType,
+};
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyTypeError, PyValueError};
+use bytes::BufMut;
+use tokio_postgres::{Column, Row};
+use tokio_postgres::types::{FromSql, IsNull, Kind, ToSql, Type};
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
+use crate::adapters;
+
+/// How TIMESTAMP/TIMESTAMPTZ columns are returned to Python
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum TimestampOutput {
+    /// `datetime.datetime` objects (default)
+    #[default]
+    DateTime,
+    /// Float seconds since the Unix epoch
+    Epoch,
+    /// Integer microseconds since the Unix epoch
+    EpochMicros,
+}
+
+impl TimestampOutput {
+    /// Parse the `timestamp_output` config value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "datetime" => Some(TimestampOutput::DateTime),
+            "epoch" => Some(TimestampOutput::Epoch),
+            "epoch_us" => Some(TimestampOutput::EpochMicros),
+            _ => None,
+        }
+    }
+
+    /// Convert microseconds since the epoch to the configured epoch representation
+    fn epoch_to_pyobject(self, py: Python<'_>, micros: i64) -> PyObject {
+        match self {
+            TimestampOutput::EpochMicros => micros.to_object(py),
+            _ => (micros as f64 / 1_000_000.0).to_object(py),
+        }
+    }
+}
+
+/// The Python object `query` builds for each result row
+#[derive(Clone, Debug, Default)]
+pub enum RowFactory<'py> {
+    /// `{column: value}` dicts (default)
+    #[default]
+    Dict,
+    /// Plain tuples in column order, skipping per-row dict construction
+    Tuple,
+    /// `collections.namedtuple` instances, allowing `row.id` access
+    NamedTuple,
+    /// `model(**row_dict)`, e.g. a dataclass or Pydantic model
+    Model(Bound<'py, PyAny>),
+}
+
+impl<'py> RowFactory<'py> {
+    /// Resolve the `row_factory` and `model` arguments of `query`
+    pub fn new(row_factory: &str, model: Option<Bound<'py, PyAny>>) -> PyResult<Self> {
+        match (row_factory, model) {
+            ("dict", Some(model)) => Ok(RowFactory::Model(model)),
+            (_, Some(_)) => Err(PyValueError::new_err("model= builds rows from dicts and can't be combined with row_factory")),
+            ("dict", None) => Ok(RowFactory::Dict),
+            ("tuple", None) => Ok(RowFactory::Tuple),
+            ("namedtuple", None) => Ok(RowFactory::NamedTuple),
+            (other, None) => Err(PyValueError::new_err(format!(
+                "Invalid row_factory '{}'. Use 'dict', 'tuple' or 'namedtuple'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Options controlling how result rows are converted to Python objects
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecodeOptions {
+    pub timestamp_output: TimestampOutput,
+    /// Return BYTEA columns as a `memoryview` over a `bytearray` instead of `bytes`
+    pub bytea_as_memoryview: bool,
+}
+
 /// A Python value that can be converted to PostgreSQL types
 #[derive(Debug, Clone)]
 pub enum PyValue {
@@ -18,35 +98,149 @@ pub enum PyValue {
     String(String),
     Bytes(Vec<u8>),
     Uuid(Uuid),
+    Decimal(Decimal),
+    /// An int past the i64 range, as its decimal digits
+    BigInt(String),
     Json(serde_json::Value),
     Date(NaiveDate),
+    Time(NaiveTime),
+    /// A naive `datetime.datetime`
     DateTime(NaiveDateTime),
+    /// A tz-aware `datetime.datetime`, normalized to UTC
     DateTimeUtc(DateTime<Utc>),
     List(Vec<PyValue>),
+    /// A NULL declared with an explicit type, see [`Null`]
+    TypedNull(Type),
+}
+
+/// A NULL parameter with a declared type
+///
+/// Plain `None` leaves the type for the server to infer, which fails for
+/// overloaded functions or bare `$1` comparisons:
+///
+/// ```python
+/// pool.query("SELECT * FROM t WHERE $1 IS NULL OR col = $1", [Null("int4")])
+/// ```
+#[pyclass(frozen)]
+#[derive(Clone)]
+pub struct Null {
+    ty: Type,
+}
+
+#[pymethods]
+impl Null {
+    /// Accepts a type name such as "int4", "text" or "uuid[]", or a type OID
+    #[new]
+    fn new(type_name: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let ty = if let Ok(oid) = type_name.extract::<u32>() {
+            Type::from_oid(oid).ok_or_else(|| PyValueError::new_err(format!("Unknown type OID {}", oid)))?
+        } else {
+            let name = type_name.extract::<String>()?;
+            type_from_name(&name).ok_or_else(|| PyValueError::new_err(format!("Unknown type name '{}'", name)))?
+        };
+        Ok(Null { ty })
+    }
+
+    /// The declared type's name
+    #[getter]
+    fn type_name(&self) -> &str {
+        self.ty.name()
+    }
+
+    /// The declared type's OID
+    #[getter]
+    fn oid(&self) -> u32 {
+        self.ty.oid()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Null('{}')", self.ty.name())
+    }
+}
+
+/// Built-in types that can be named in `Null(...)`
+const NAMED_TYPES: &[Type] = &[
+    Type::BOOL, Type::INT2, Type::INT4, Type::INT8, Type::FLOAT4, Type::FLOAT8, Type::NUMERIC,
+    Type::TEXT, Type::VARCHAR, Type::BPCHAR, Type::NAME, Type::BYTEA, Type::UUID, Type::JSON, Type::JSONB,
+    Type::DATE, Type::TIME, Type::TIMESTAMP, Type::TIMESTAMPTZ, Type::INTERVAL, Type::INET, Type::CIDR,
+    Type::MACADDR, Type::MACADDR8, Type::XML,
+    Type::POINT, Type::BOX, Type::CIRCLE, Type::POLYGON, Type::OID,
+    Type::BOOL_ARRAY, Type::INT2_ARRAY, Type::INT4_ARRAY, Type::INT8_ARRAY, Type::FLOAT4_ARRAY,
+    Type::FLOAT8_ARRAY, Type::NUMERIC_ARRAY, Type::TEXT_ARRAY, Type::VARCHAR_ARRAY, Type::BYTEA_ARRAY,
+    Type::UUID_ARRAY, Type::JSONB_ARRAY, Type::DATE_ARRAY, Type::TIMESTAMP_ARRAY, Type::TIMESTAMPTZ_ARRAY,
+];
+
+/// Resolve a type name, accepting SQL spellings like "integer" and "text[]"
+fn type_from_name(name: &str) -> Option<Type> {
+    let name = name.trim().to_ascii_lowercase();
+    let (base, array) = match name.strip_suffix("[]") {
+        Some(base) => (base.trim_end(), true),
+        None => (name.as_str(), false),
+    };
+    let base = match base {
+        "boolean" => "bool",
+        "smallint" => "int2",
+        "integer" | "int" => "int4",
+        "bigint" => "int8",
+        "real" => "float4",
+        "double precision" => "float8",
+        "decimal" => "numeric",
+        "character varying" => "varchar",
+        "character" | "char" => "bpchar",
+        "timestamp without time zone" => "timestamp",
+        "timestamp with time zone" => "timestamptz",
+        "time without time zone" => "time",
+        other => other,
+    };
+    let name = if array { format!("_{}", base) } else { base.to_string() };
+    NAMED_TYPES.iter().find(|ty| ty.name() == name).cloned()
 }
 
 impl<'py> FromPyObject<'py> for PyValue {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         if ob.is_none() {
             Ok(PyValue::None)
+        } else if let Some(adapted) = adapters::adapt(ob)? {
+            adapted.extract()
+        } else if let Ok(null) = ob.downcast::<Null>() {
+            Ok(PyValue::TypedNull(null.get().ty.clone()))
         } else if let Ok(b) = ob.extract::<bool>() {
             Ok(PyValue::Bool(b))
         } else if let Ok(i) = ob.extract::<i64>() {
             Ok(PyValue::Int(i))
+        } else if ob.is_instance_of::<PyInt>() {
+            // Past the i64 range: bound as NUMERIC rather than rounded through float
+            big_int_to_pyvalue(ob)
+        } else if let Some(decimal) = decimal_to_pyvalue(ob)? {
+            // Checked before floats: Decimal implements __float__
+            Ok(decimal)
         } else if let Ok(f) = ob.extract::<f64>() {
             Ok(PyValue::Float(f))
         } else if let Ok(s) = ob.extract::<String>() {
-            // Try to parse as UUID first
-            if let Ok(uuid) = Uuid::parse_str(&s) {
-                Ok(PyValue::Uuid(uuid))
-            } else {
-                Ok(PyValue::String(s))
-            }
-        } else if let Ok(bytes) = ob.extract::<Vec<u8>>() {
-            Ok(PyValue::Bytes(bytes))
+            // The target column decides whether a string is a UUID (see `to_sql`)
+            Ok(PyValue::String(s))
+        } else if let Some(value) = datetime_to_pyvalue(ob)? {
+            Ok(value)
+        } else if ob.is_instance_of::<PyDict>() {
+            Ok(PyValue::Json(py_to_json(ob)?))
         } else if let Ok(list) = ob.downcast::<PyList>() {
+            if list.iter().any(|item| item.is_instance_of::<PyDict>()) {
+                // e.g. [{"id": 1}, {"id": 2}] for a jsonb column
+                return Ok(PyValue::Json(py_to_json(ob)?));
+            }
             let items: PyResult<Vec<PyValue>> = list.iter().map(|item| item.extract()).collect();
             Ok(PyValue::List(items?))
+        } else if let Ok(tuple) = ob.downcast::<PyTuple>() {
+            // Tuples are sequences too, e.g. (x, y) for a point
+            let items: PyResult<Vec<PyValue>> = tuple.iter().map(|item| item.extract()).collect();
+            Ok(PyValue::List(items?))
+        } else if let Ok(bytes) = ob.extract::<Vec<u8>>() {
+            Ok(PyValue::Bytes(bytes))
+        } else if let Some(uuid) = uuid_to_pyvalue(ob)? {
+            Ok(uuid)
+        } else if ob.is_instance(cached_class(ob.py(), &ENUM_CLASS, "enum", "Enum")?)? {
+            // Enum members bind as their value, e.g. a label of a Postgres enum
+            ob.getattr("value")?.extract()
         } else {
             // Try JSON serialization as fallback
             let json_mod = ob.py().import_bound("json")?;
@@ -58,30 +252,343 @@ impl<'py> FromPyObject<'py> for PyValue {
     }
 }
 
+/// Convert a dict/list structure straight into a JSON value, without a `json.dumps` round trip
+///
+/// Integers and `Decimal`s keep their exact digits.
+fn py_to_json(ob: &Bound<'_, PyAny>) -> PyResult<serde_json::Value> {
+    use serde_json::{Map, Number, Value};
+
+    let number = |text: String| {
+        text.parse::<Number>()
+            .map(Value::Number)
+            .map_err(|_| PyValueError::new_err(format!("Cannot convert {} to a JSON number", text)))
+    };
+
+    if ob.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(b) = ob.downcast::<PyBool>() {
+        Ok(Value::Bool(b.is_true()))
+    } else if ob.is_instance_of::<PyInt>() {
+        number(ob.str()?.to_string())
+    } else if let Ok(f) = ob.downcast::<PyFloat>() {
+        Number::from_f64(f.value())
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err(format!("Cannot convert {} to a JSON number", f)))
+    } else if let Ok(s) = ob.downcast::<PyString>() {
+        Ok(Value::String(s.to_str()?.to_owned()))
+    } else if let Ok(dict) = ob.downcast::<PyDict>() {
+        let mut map = Map::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            let key = if let Ok(key) = key.downcast::<PyString>() {
+                key.to_str()?.to_owned()
+            } else if key.is_instance_of::<PyInt>() && !key.is_instance_of::<PyBool>() {
+                key.str()?.to_string()
+            } else {
+                return Err(PyTypeError::new_err(format!("JSON object keys must be str or int, not {}", key.get_type().name()?)));
+            };
+            map.insert(key, py_to_json(&value)?);
+        }
+        Ok(Value::Object(map))
+    } else if let Ok(list) = ob.downcast::<PyList>() {
+        list.iter().map(|item| py_to_json(&item)).collect::<PyResult<_>>().map(Value::Array)
+    } else if let Ok(tuple) = ob.downcast::<PyTuple>() {
+        tuple.iter().map(|item| py_to_json(&item)).collect::<PyResult<_>>().map(Value::Array)
+    } else if ob.is_instance(cached_class(ob.py(), &DECIMAL_CLASS, "decimal", "Decimal")?)? {
+        number(ob.str()?.to_string())
+    } else {
+        Err(PyTypeError::new_err(format!("Object of type {} is not JSON serializable", ob.get_type().name()?)))
+    }
+}
+
+/// Convert `datetime.datetime`/`date`/`time` objects, respecting tzinfo
+fn datetime_to_pyvalue(ob: &Bound<'_, PyAny>) -> PyResult<Option<PyValue>> {
+    let invalid = || PyValueError::new_err(format!("Cannot convert {} to a PostgreSQL date/time", ob));
+
+    // datetime is a subclass of date, so it must be checked first
+    if let Ok(dt) = ob.downcast::<PyDateTime>() {
+        let date = NaiveDate::from_ymd_opt(dt.get_year(), dt.get_month().into(), dt.get_day().into()).ok_or_else(invalid)?;
+        let time = NaiveTime::from_hms_micro_opt(
+            dt.get_hour().into(),
+            dt.get_minute().into(),
+            dt.get_second().into(),
+            dt.get_microsecond(),
+        ).ok_or_else(invalid)?;
+        let naive = NaiveDateTime::new(date, time);
+
+        let offset = ob.call_method0("utcoffset")?;
+        if offset.is_none() {
+            return Ok(Some(PyValue::DateTime(naive)));
+        }
+        let offset = offset.downcast::<PyDelta>()?;
+        let offset = chrono::Duration::days(offset.get_days().into())
+            + chrono::Duration::seconds(offset.get_seconds().into())
+            + chrono::Duration::microseconds(offset.get_microseconds().into());
+        return Ok(Some(PyValue::DateTimeUtc((naive - offset).and_utc())));
+    }
+    if let Ok(d) = ob.downcast::<PyDate>() {
+        let date = NaiveDate::from_ymd_opt(d.get_year(), d.get_month().into(), d.get_day().into()).ok_or_else(invalid)?;
+        return Ok(Some(PyValue::Date(date)));
+    }
+    if let Ok(t) = ob.downcast::<PyTime>() {
+        let time = NaiveTime::from_hms_micro_opt(
+            t.get_hour().into(),
+            t.get_minute().into(),
+            t.get_second().into(),
+            t.get_microsecond(),
+        ).ok_or_else(invalid)?;
+        return Ok(Some(PyValue::Time(time)));
+    }
+    Ok(None)
+}
+
+static UUID_CLASS: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+static DECIMAL_CLASS: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+static ENUM_CLASS: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+/// Look up a Python class once and keep it for later isinstance checks
+fn cached_class<'py>(
+    py: Python<'py>,
+    cell: &'static GILOnceCell<Py<PyType>>,
+    module: &str,
+    name: &str,
+) -> PyResult<&'py Bound<'py, PyType>> {
+    let class = cell.get_or_try_init(py, || {
+        let class = py.import_bound(module)?.getattr(name)?.downcast_into::<PyType>()?;
+        Ok::<_, PyErr>(class.unbind())
+    })?;
+    Ok(class.bind(py))
+}
+
+/// Convert `uuid.UUID` instances
+fn uuid_to_pyvalue(ob: &Bound<'_, PyAny>) -> PyResult<Option<PyValue>> {
+    if !ob.is_instance(cached_class(ob.py(), &UUID_CLASS, "uuid", "UUID")?)? {
+        return Ok(None);
+    }
+    let bytes: Vec<u8> = ob.getattr("bytes")?.extract()?;
+    let uuid = Uuid::from_slice(&bytes).map_err(|e| PyValueError::new_err(format!("Invalid UUID: {}", e)))?;
+    Ok(Some(PyValue::Uuid(uuid)))
+}
+
+/// Convert `decimal.Decimal` instances without going through float
+fn decimal_to_pyvalue(ob: &Bound<'_, PyAny>) -> PyResult<Option<PyValue>> {
+    if ob.is_instance_of::<PyFloat>() || !ob.is_instance(cached_class(ob.py(), &DECIMAL_CLASS, "decimal", "Decimal")?)? {
+        return Ok(None);
+    }
+    let text = ob.str()?.to_string();
+    let decimal = Decimal::from_str_exact(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|e| PyValueError::new_err(format!("Cannot convert Decimal('{}') to NUMERIC: {}", text, e)))?;
+    Ok(Some(PyValue::Decimal(decimal)))
+}
+
+/// Convert an int too large for i64, keeping every digit
+fn big_int_to_pyvalue(ob: &Bound<'_, PyAny>) -> PyResult<PyValue> {
+    Ok(PyValue::BigInt(ob.str()?.to_string()))
+}
+
+/// Write an integer's decimal digits in NUMERIC's binary format: base-10000
+/// digit groups after the group count, the weight of the first group, the sign
+/// and the display scale
+fn big_int_to_sql(digits: &str, out: &mut bytes::BytesMut) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+    let (negative, magnitude) = match digits.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, digits),
+    };
+    let magnitude = magnitude.trim_start_matches('0');
+    let mut groups: Vec<i16> = magnitude.as_bytes()
+        .rchunks(4)
+        .map(|chunk| chunk.iter().fold(0, |group, digit| group * 10 + i16::from(digit - b'0')))
+        .collect();
+    groups.reverse();
+    let weight = i16::try_from(groups.len()).map_err(|_| format!("{} has too many digits for numeric", digits))? - 1;
+    // Trailing zero groups are implied by the weight
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    out.put_i16(groups.len() as i16);
+    out.put_i16(weight);
+    out.put_u16(if negative { 0x4000 } else { 0 });
+    out.put_i16(0);
+    for group in groups {
+        out.put_i16(group);
+    }
+    Ok(IsNull::No)
+}
+
+/// Build a Python `decimal.Decimal` from its exact string form
+fn decimal_to_pyobject(py: Python<'_>, text: &str) -> PyResult<PyObject> {
+    Ok(cached_class(py, &DECIMAL_CLASS, "decimal", "Decimal")?.call1((text,))?.unbind())
+}
+
+/// The exact string form of a NUMERIC in its binary format, e.g. "-12.50" or "NaN"
+///
+/// The header is the digit count, the weight of the first base-10000 digit, the
+/// sign and the display scale; digits are read directly, so there is no limit on
+/// their number like `rust_decimal`'s 28.
+fn numeric_to_string(raw: &[u8]) -> Result<String, String> {
+    let field = |i: usize| {
+        raw.get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| format!("truncated value ({} bytes)", raw.len()))
+    };
+    let ndigits = usize::from(field(0)?);
+    let weight = i32::from(field(1)? as i16);
+    let sign = field(2)?;
+    let dscale = usize::from(field(3)?);
+    match sign {
+        0x0000 | 0x4000 => {}
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        other => return Err(format!("unknown sign {:#06x}", other)),
+    }
+    let digits = (0..ndigits).map(|k| field(4 + k)).collect::<Result<Vec<_>, _>>()?;
+    // Digit k has weight `weight - k`; those past either end are zero
+    let digit = |k: i32| usize::try_from(k).ok().and_then(|k| digits.get(k)).copied().unwrap_or(0);
+
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    match weight < 0 {
+        true => text.push('0'),
+        false => {
+            text.push_str(&digit(0).to_string());
+            for k in 1..=weight {
+                text.push_str(&format!("{:04}", digit(k)));
+            }
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut k = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit(k)));
+            k += 1;
+        }
+        fraction.truncate(dscale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
+    Ok(text)
+}
+
+/// Parameter types to declare when preparing `params`
+///
+/// Empty unless a typed NULL is present, in which case the other parameters are
+/// left unspecified (OID 0) for the server to infer as usual.
+pub fn param_types(params: &[PyValue]) -> Vec<Type> {
+    if !params.iter().any(|p| matches!(p, PyValue::TypedNull(_))) {
+        return Vec::new();
+    }
+    params.iter().map(|p| match p {
+        PyValue::TypedNull(ty) => ty.clone(),
+        _ => Type::new("unspecified".to_string(), 0, Kind::Pseudo, "pg_catalog".to_string()),
+    }).collect()
+}
+
+impl PyValue {
+    /// Collect the numbers of a (possibly nested) list in order, e.g. `[(1, 2), (3, 4)]` -> `[1, 2, 3, 4]`
+    fn flatten_coords(&self, coords: &mut Vec<f64>) -> bool {
+        match self {
+            PyValue::Int(i) => coords.push(*i as f64),
+            PyValue::Float(f) => coords.push(*f),
+            PyValue::List(items) => return items.iter().all(|item| item.flatten_coords(coords)),
+            PyValue::Json(json) => return json_flatten_coords(json, coords),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Encode a point/box/circle/polygon in the PostgreSQL binary format
+    fn geometric_to_sql(&self, ty: &Type, out: &mut bytes::BytesMut) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        let mut coords = Vec::new();
+        let valid = self.flatten_coords(&mut coords) && match *ty {
+            Type::POINT => coords.len() == 2,
+            Type::BOX => coords.len() == 4,
+            Type::CIRCLE => coords.len() == 3,
+            _ => !coords.is_empty() && coords.len() % 2 == 0,
+        };
+        if !valid {
+            return Err(format!(
+                "Cannot convert {:?} to {}: expected point (x, y), box ((x1, y1), (x2, y2)), circle ((x, y), r) or polygon [(x, y), ...]",
+                self, ty
+            ).into());
+        }
+
+        if *ty == Type::POLYGON {
+            out.put_i32(i32::try_from(coords.len() / 2)?);
+        }
+        for c in coords {
+            out.put_f64(c);
+        }
+        Ok(IsNull::No)
+    }
+}
+
+fn json_flatten_coords(json: &serde_json::Value, coords: &mut Vec<f64>) -> bool {
+    match json {
+        serde_json::Value::Number(n) => match n.as_f64() {
+            Some(f) => coords.push(f),
+            None => return false,
+        },
+        serde_json::Value::Array(items) => return items.iter().all(|item| json_flatten_coords(item, coords)),
+        _ => return false,
+    }
+    true
+}
+
+fn is_geometric(ty: &Type) -> bool {
+    matches!(*ty, Type::POINT | Type::BOX | Type::CIRCLE | Type::POLYGON)
+}
+
 impl ToSql for PyValue {
     fn to_sql(&self, ty: &Type, out: &mut bytes::BytesMut) -> Result<tokio_postgres::types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        if is_geometric(ty) && matches!(self, PyValue::List(_) | PyValue::Json(_)) {
+            return self.geometric_to_sql(ty, out);
+        }
+
         match self {
-            PyValue::None => Ok(tokio_postgres::types::IsNull::Yes),
+            PyValue::None | PyValue::TypedNull(_) => Ok(tokio_postgres::types::IsNull::Yes),
             PyValue::Bool(b) => b.to_sql(ty, out),
+            // Narrowed for parameters the server inferred as a smaller integer
+            PyValue::Int(i) if *ty == Type::INT2 => i16::try_from(*i).map_err(|_| format!("{} is out of range for smallint", i))?.to_sql(ty, out),
+            PyValue::Int(i) if *ty == Type::INT4 => i32::try_from(*i).map_err(|_| format!("{} is out of range for integer", i))?.to_sql(ty, out),
             PyValue::Int(i) => i.to_sql(ty, out),
             PyValue::Float(f) => f.to_sql(ty, out),
+            PyValue::String(s) if *ty == Type::UUID => {
+                Uuid::parse_str(s).map_err(|e| format!("Invalid UUID string '{}': {}", s, e))?.to_sql(ty, out)
+            }
+            PyValue::String(s) if matches!(*ty, Type::MACADDR | Type::MACADDR8) => {
+                out.put_slice(&parse_macaddr(ty, s)?);
+                Ok(IsNull::No)
+            }
             PyValue::String(s) => s.to_sql(ty, out),
             PyValue::Bytes(b) => b.to_sql(ty, out),
             PyValue::Uuid(u) => u.to_sql(ty, out),
+            PyValue::Decimal(d) => d.to_sql(ty, out),
+            PyValue::BigInt(digits) if *ty == Type::NUMERIC => big_int_to_sql(digits, out),
+            PyValue::BigInt(digits) if matches!(*ty, Type::TEXT | Type::VARCHAR | Type::BPCHAR) => digits.to_sql(ty, out),
+            PyValue::BigInt(digits) => Err(format!("{} is out of range for {}", digits, ty).into()),
             PyValue::Json(j) => j.to_sql(ty, out),
             PyValue::Date(d) => d.to_sql(ty, out),
+            PyValue::Time(t) => t.to_sql(ty, out),
             PyValue::DateTime(dt) => dt.to_sql(ty, out),
             PyValue::DateTimeUtc(dt) => dt.to_sql(ty, out),
             PyValue::List(l) => {
                 // Handle arrays - for simplicity, convert to JSON
-                let json = serde_json::to_value(l.iter().map(|v| match v {
+                let items = l.iter().map(|v| Ok(match v {
                     PyValue::String(s) => serde_json::Value::String(s.clone()),
                     PyValue::Int(i) => serde_json::Value::Number((*i).into()),
-                    PyValue::Float(f) => serde_json::Value::Number(serde_json::Number::from_f64(*f).unwrap_or(0.into())),
+                    PyValue::BigInt(digits) => serde_json::Value::Number(digits.parse()?),
+                    // JSON has no NaN or Infinity
+                    PyValue::Float(f) => serde_json::Value::Number(
+                        serde_json::Number::from_f64(*f).ok_or_else(|| format!("Cannot convert {} to a JSON number", f))?,
+                    ),
                     PyValue::Bool(b) => serde_json::Value::Bool(*b),
                     _ => serde_json::Value::Null,
-                }).collect::<Vec<_>>()).unwrap_or(serde_json::Value::Null);
-                json.to_sql(ty, out)
+                })).collect::<Result<Vec<_>, Box<dyn std::error::Error + Sync + Send>>>()?;
+                serde_json::Value::Array(items).to_sql(ty, out)
             }
         }
     }
@@ -93,139 +600,435 @@ impl ToSql for PyValue {
     tokio_postgres::types::to_sql_checked!();
 }
 
+/// The undecoded bytes of a column value, accepted for any type
+struct RawValue<'a>(&'a [u8]);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(RawValue(raw))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+/// Read consecutive big-endian (x, y) float pairs
+fn read_points(buf: &[u8]) -> Vec<(f64, f64)> {
+    buf.chunks_exact(16)
+        .map(|p| {
+            let x = f64::from_be_bytes(p[..8].try_into().unwrap());
+            let y = f64::from_be_bytes(p[8..].try_into().unwrap());
+            (x, y)
+        })
+        .collect()
+}
+
+/// Decode point/box/circle/polygon from the PostgreSQL binary format
+fn geometric_to_pyobject(py: Python<'_>, pg_type: &Type, raw: &[u8]) -> PyResult<PyObject> {
+    let invalid = || PyValueError::new_err(format!("Invalid binary value for {} ({} bytes)", pg_type, raw.len()));
+
+    match *pg_type {
+        Type::POINT if raw.len() == 16 => Ok(read_points(raw)[0].to_object(py)),
+        // Stored as the upper-right corner followed by the lower-left corner
+        Type::BOX if raw.len() == 32 => {
+            let corners = read_points(raw);
+            Ok((corners[0], corners[1]).to_object(py))
+        }
+        Type::CIRCLE if raw.len() == 24 => {
+            let center = read_points(&raw[..16])[0];
+            let radius = f64::from_be_bytes(raw[16..].try_into().unwrap());
+            Ok((center, radius).to_object(py))
+        }
+        Type::POLYGON if raw.len() >= 4 => {
+            let npts = i32::from_be_bytes(raw[..4].try_into().unwrap());
+            if usize::try_from(npts).ok().map(|n| n * 16) != Some(raw.len() - 4) {
+                return Err(invalid());
+            }
+            Ok(read_points(&raw[4..]).to_object(py))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Format a macaddr/macaddr8 value as lowercase colon-separated hex, e.g. "08:00:2b:01:02:03"
+fn format_macaddr(pg_type: &Type, raw: &[u8]) -> PyResult<String> {
+    let expected = if *pg_type == Type::MACADDR { 6 } else { 8 };
+    if raw.len() != expected {
+        return Err(PyValueError::new_err(format!("Invalid binary value for {} ({} bytes)", pg_type, raw.len())));
+    }
+    Ok(raw.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"))
+}
+
+/// Parse a ':' or '-' separated hardware address for a macaddr/macaddr8 parameter
+///
+/// Like the server, a 6-byte address for macaddr8 gets FF:FE inserted in the middle.
+fn parse_macaddr(ty: &Type, s: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid {} string '{}'", ty, s);
+    let mut bytes = s
+        .split([':', '-'])
+        .map(|part| match part.len() {
+            2 => u8::from_str_radix(part, 16).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match (bytes.len(), *ty == Type::MACADDR8) {
+        (6, false) | (8, true) => Ok(bytes),
+        (6, true) => {
+            bytes.splice(3..3, [0xff, 0xfe]);
+            Ok(bytes)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Keys for a result's columns: their names, with repeats suffixed `_1`, `_2`, ...
+///
+/// `SELECT a.id, b.id` gives `id` and `id_1`, so neither value is dropped from a
+/// dict. A suffixed key never collides with another column's name.
+pub fn unique_names<'a>(names: &[&'a str]) -> Vec<Cow<'a, str>> {
+    let mut keys: Vec<Cow<'a, str>> = Vec::with_capacity(names.len());
+    for (i, &name) in names.iter().enumerate() {
+        if !names[..i].contains(&name) {
+            keys.push(Cow::Borrowed(name));
+            continue;
+        }
+        let key = (1..)
+            .map(|n| format!("{}_{}", name, n))
+            .find(|key| !names.contains(&key.as_str()) && !keys.iter().any(|k| k == key))
+            .expect("some suffix is free");
+        keys.push(Cow::Owned(key));
+    }
+    keys
+}
+
+/// Dict keys for `columns`, see [`unique_names`]
+pub fn column_keys(columns: &[Column]) -> Vec<Cow<'_, str>> {
+    unique_names(&columns.iter().map(Column::name).collect::<Vec<_>>())
+}
+
+/// What `query` returns: its rows, or `(columns, rows)` when `columns` are given
+pub fn query_result<'py>(py: Python<'py>, rows: Bound<'py, PyList>, columns: Option<Vec<Cow<'_, str>>>) -> Bound<'py, PyAny> {
+    match columns {
+        Some(columns) => PyTuple::new_bound(py, [PyList::new_bound(py, columns).into_any(), rows.into_any()]).into_any(),
+        None => rows.into_any(),
+    }
+}
+
 /// Convert a PostgreSQL row to a Python dictionary
-pub fn row_to_dict<'py>(py: Python<'py>, row: &Row) -> PyResult<Bound<'py, PyDict>> {
+pub fn row_to_dict<'py>(py: Python<'py>, row: &Row, opts: &DecodeOptions) -> PyResult<Bound<'py, PyDict>> {
+    row_to_keyed_dict(py, row, opts, &column_keys(row.columns()))
+}
+
+/// Convert a row to a dictionary under `keys`, computed once per result
+fn row_to_keyed_dict<'py>(py: Python<'py>, row: &Row, opts: &DecodeOptions, keys: &[Cow<'_, str>]) -> PyResult<Bound<'py, PyDict>> {
     let dict = PyDict::new_bound(py);
     
-    for (i, column) in row.columns().iter().enumerate() {
-        let name = column.name();
-        let value = column_to_pyobject(py, row, i, column.type_())?;
-        dict.set_item(name, value)?;
+    for (i, (column, key)) in row.columns().iter().zip(keys).enumerate() {
+        let value = column_to_pyobject(py, row, i, column.type_(), opts)?;
+        dict.set_item(key.as_ref(), value)?;
     }
     
     Ok(dict)
 }
 
-/// Convert a single column value to a Python object
-fn column_to_pyobject<'py>(py: Python<'py>, row: &Row, idx: usize, pg_type: &Type) -> PyResult<PyObject> {
-    // Handle NULL values
-    let raw_value: Option<&[u8]> = row.try_get(idx).ok().flatten();
-    if raw_value.is_none() {
-        return Ok(py.None());
+/// Convert a row to a Python tuple in column order
+pub fn row_to_tuple<'py>(py: Python<'py>, row: &Row, opts: &DecodeOptions) -> PyResult<Bound<'py, PyTuple>> {
+    let values = row.columns().iter().enumerate()
+        .map(|(i, column)| column_to_pyobject(py, row, i, column.type_(), opts))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyTuple::new_bound(py, values))
+}
+
+/// Convert result rows to a Python list, building each row with `factory`
+pub fn rows_to_list<'py>(py: Python<'py>, rows: &[Row], opts: &DecodeOptions, factory: &RowFactory<'py>) -> PyResult<Bound<'py, PyList>> {
+    let result = PyList::empty_bound(py);
+    let row_class = match (factory, rows.first()) {
+        (RowFactory::NamedTuple, Some(first)) => Some(namedtuple_class(py, first.columns().iter().map(Column::name))?),
+        _ => None,
+    };
+    let keys = rows.first().map(|first| column_keys(first.columns())).unwrap_or_default();
+    for (i, row) in rows.iter().enumerate() {
+        match (factory, &row_class) {
+            (RowFactory::Dict, _) => result.append(row_to_keyed_dict(py, row, opts, &keys)?)?,
+            (RowFactory::Model(model), _) => result.append(dict_to_model(py, &row_to_keyed_dict(py, row, opts, &keys)?, model, i)?)?,
+            (_, Some(class)) => result.append(class.call1(row_to_tuple(py, row, opts)?)?)?,
+            (_, None) => result.append(row_to_tuple(py, row, opts)?)?,
+        }
+    }
+    Ok(result)
+}
+
+/// Call `model(**dict)` for row `index`, naming the model and row if construction fails
+pub(crate) fn dict_to_model<'py>(py: Python<'py>, dict: &Bound<'py, PyDict>, model: &Bound<'py, PyAny>, index: usize) -> PyResult<Bound<'py, PyAny>> {
+    model.call((), Some(dict)).map_err(|err| {
+        let name = model.getattr("__name__").map_or_else(|_| model.to_string(), |name| name.to_string());
+        let wrapped = PyValueError::new_err(format!("Failed to build {} from row {}: {}", name, index, err));
+        wrapped.set_cause(py, Some(err));
+        wrapped
+    })
+}
+
+/// Convert result rows to `{column: [values...]}`, keyed in column order (repeated names suffixed)
+pub fn rows_to_columns<'py>(py: Python<'py>, columns: &[Column], rows: &[Row], opts: &DecodeOptions) -> PyResult<Bound<'py, PyDict>> {
+    let result = PyDict::new_bound(py);
+    for (i, (column, key)) in columns.iter().zip(column_keys(columns)).enumerate() {
+        let values = rows.iter()
+            .map(|row| column_to_pyobject(py, row, i, column.type_(), opts))
+            .collect::<PyResult<Vec<_>>>()?;
+        result.set_item(key.as_ref(), PyList::new_bound(py, values))?;
+    }
+    Ok(result)
+}
+
+/// A result column picked by position or by name
+#[derive(FromPyObject)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+/// The values of one column across all rows
+pub fn column_values<'py>(
+    py: Python<'py>,
+    columns: &[Column],
+    rows: &[Row],
+    column: &ColumnRef,
+    opts: &DecodeOptions,
+) -> PyResult<Bound<'py, PyList>> {
+    let idx = match column {
+        ColumnRef::Name(name) => columns.iter().position(|c| c.name() == name)
+            .ok_or_else(|| PyKeyError::new_err(format!("No column named '{}'", name)))?,
+        ColumnRef::Index(idx) if *idx >= columns.len() => {
+            return Err(PyIndexError::new_err(format!("Column index {} out of range for {} columns", idx, columns.len())));
+        }
+        ColumnRef::Index(idx) => *idx,
+    };
+    let pg_type = columns[idx].type_();
+    let values = rows.iter()
+        .map(|row| column_to_pyobject(py, row, idx, pg_type, opts))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new_bound(py, values))
+}
+
+/// Build a namedtuple class for a result's columns, once per query
+///
+/// Characters that can't appear in an identifier become `_`; keywords, duplicates
+/// and names that still aren't valid are renamed positionally (`_0`, `_1`, ...).
+pub(crate) fn namedtuple_class<'py, 'a>(py: Python<'py>, columns: impl Iterator<Item = &'a str>) -> PyResult<Bound<'py, PyAny>> {
+    let names: Vec<String> = columns
+        .map(|name| name.chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect())
+        .collect();
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("rename", true)?;
+    py.import_bound("collections")?.getattr("namedtuple")?.call(("Row", names), Some(&kwargs))
+}
+
+/// Convert a single column value to a Python object, applying any registered converter
+fn column_to_pyobject(py: Python<'_>, row: &Row, idx: usize, pg_type: &Type, opts: &DecodeOptions) -> PyResult<PyObject> {
+    adapters::convert(py, pg_type.oid(), decode_column(py, row, idx, pg_type, opts)?)
+}
+
+/// Decode a single column value with the built-in type mapping
+fn decode_column(py: Python<'_>, row: &Row, idx: usize, pg_type: &Type, opts: &DecodeOptions) -> PyResult<PyObject> {
+    let raw_value: Option<RawValue> = row.try_get(idx).ok().flatten();
+    match raw_value {
+        Some(RawValue(raw)) => decode_value(py, pg_type, raw, opts),
+        None => Ok(py.None()),
     }
+}
+
+/// Parse `raw` as `T`, as `row.get` would
+fn from_raw<'a, T: FromSql<'a>>(pg_type: &Type, raw: &'a [u8]) -> PyResult<T> {
+    T::from_sql(pg_type, raw).map_err(|e| PyValueError::new_err(format!("Cannot decode {} value: {}", pg_type, e)))
+}
 
+/// Decode one non-NULL binary value, a whole column or an element of an array
+fn decode_value(py: Python<'_>, pg_type: &Type, raw: &[u8], opts: &DecodeOptions) -> PyResult<PyObject> {
     match *pg_type {
+        Type::POINT | Type::BOX | Type::CIRCLE | Type::POLYGON => geometric_to_pyobject(py, pg_type, raw),
+        Type::MACADDR | Type::MACADDR8 => Ok(format_macaddr(pg_type, raw)?.to_object(py)),
+        // Sent as text in the client encoding, left unparsed
+        Type::XML => Ok(from_raw::<&str>(pg_type, raw)?.to_object(py)),
+        // Extension types, so their OIDs differ per database. The ltree family is sent
+        // with a leading version byte, which `&str` strips (and adds for parameters).
+        _ if matches!(pg_type.name(), "citext" | "ltree" | "lquery" | "ltxtquery") => {
+            Ok(from_raw::<&str>(pg_type, raw)?.to_object(py))
+        }
+        // PostGIS sends EWKB, which shapely.wkb.loads reads; bytes parameters are sent as-is
+        _ if matches!(pg_type.name(), "geometry" | "geography") => Ok(PyBytes::new_bound(py, raw).into_any().unbind()),
         Type::BOOL => {
-            let v: Option<bool> = row.get(idx);
-            Ok(v.map(|b| b.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<bool>(pg_type, raw)?.to_object(py))
         }
         Type::INT2 => {
-            let v: Option<i16> = row.get(idx);
-            Ok(v.map(|i| i.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<i16>(pg_type, raw)?.to_object(py))
         }
         Type::INT4 => {
-            let v: Option<i32> = row.get(idx);
-            Ok(v.map(|i| i.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<i32>(pg_type, raw)?.to_object(py))
         }
         Type::INT8 => {
-            let v: Option<i64> = row.get(idx);
-            Ok(v.map(|i| i.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<i64>(pg_type, raw)?.to_object(py))
         }
         Type::FLOAT4 => {
-            let v: Option<f32> = row.get(idx);
-            Ok(v.map(|f| f.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<f32>(pg_type, raw)?.to_object(py))
+        }
+        Type::FLOAT8 => {
+            Ok(from_raw::<f64>(pg_type, raw)?.to_object(py))
         }
-        Type::FLOAT8 | Type::NUMERIC => {
-            let v: Option<f64> = row.get(idx);
-            Ok(v.map(|f| f.to_object(py)).unwrap_or_else(|| py.None()))
+        Type::NUMERIC => {
+            let text = numeric_to_string(raw)
+                .map_err(|e| PyValueError::new_err(format!("Cannot decode NUMERIC column: {}", e)))?;
+            decimal_to_pyobject(py, &text)
         }
         Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
-            let v: Option<String> = row.get(idx);
-            Ok(v.map(|s| s.to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<&str>(pg_type, raw)?.to_object(py))
         }
         Type::BYTEA => {
-            let v: Option<Vec<u8>> = row.get(idx);
-            Ok(v.map(|b| b.to_object(py)).unwrap_or_else(|| py.None()))
+            // Borrowed from the row, so the only copy is the Python object
+            match opts.bytea_as_memoryview {
+                true => Ok(PyMemoryView::from_bound(&PyByteArray::new_bound(py, raw))?.into_any().unbind()),
+                false => Ok(PyBytes::new_bound(py, raw).into_any().unbind()),
+            }
         }
         Type::UUID => {
-            let v: Option<Uuid> = row.get(idx);
-            Ok(v.map(|u| u.to_string().to_object(py)).unwrap_or_else(|| py.None()))
+            Ok(from_raw::<Uuid>(pg_type, raw)?.to_string().to_object(py))
         }
         Type::JSON | Type::JSONB => {
-            let v: Option<serde_json::Value> = row.get(idx);
-            match v {
-                Some(json) => {
-                    let json_str = serde_json::to_string(&json)
-                        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
-                    let json_mod = py.import_bound("json")?;
-                    let py_obj = json_mod.call_method1("loads", (json_str,))?;
-                    Ok(py_obj.unbind())
-                }
-                None => Ok(py.None()),
-            }
+            let json = from_raw::<serde_json::Value>(pg_type, raw)?;
+            let json_str = serde_json::to_string(&json)
+                .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+            let json_mod = py.import_bound("json")?;
+            let py_obj = json_mod.call_method1("loads", (json_str,))?;
+            Ok(py_obj.unbind())
         }
         Type::DATE => {
-            let v: Option<NaiveDate> = row.get(idx);
-            match v {
-                Some(d) => {
-                    let datetime = py.import_bound("datetime")?;
-                    let date = datetime.getattr("date")?.call1((d.year(), d.month(), d.day()))?;
-                    Ok(date.unbind())
-                }
-                None => Ok(py.None()),
-            }
+            let d = from_raw::<NaiveDate>(pg_type, raw)?;
+            let datetime = py.import_bound("datetime")?;
+            let date = datetime.getattr("date")?.call1((d.year(), d.month(), d.day()))?;
+            Ok(date.unbind())
         }
         Type::TIME => {
-            let v: Option<NaiveTime> = row.get(idx);
-            match v {
-                Some(t) => {
-                    let datetime = py.import_bound("datetime")?;
-                    let time = datetime.getattr("time")?.call1((t.hour(), t.minute(), t.second(), t.nanosecond() / 1000))?;
-                    Ok(time.unbind())
-                }
-                None => Ok(py.None()),
-            }
+            let t = from_raw::<NaiveTime>(pg_type, raw)?;
+            let datetime = py.import_bound("datetime")?;
+            let time = datetime.getattr("time")?.call1((t.hour(), t.minute(), t.second(), t.nanosecond() / 1000))?;
+            Ok(time.unbind())
         }
         Type::TIMESTAMP => {
-            let v: Option<NaiveDateTime> = row.get(idx);
-            match v {
-                Some(dt) => {
-                    let datetime = py.import_bound("datetime")?;
-                    let py_dt = datetime.getattr("datetime")?.call1((
-                        dt.date().year(),
-                        dt.date().month(),
-                        dt.date().day(),
-                        dt.time().hour(),
-                        dt.time().minute(),
-                        dt.time().second(),
-                        dt.time().nanosecond() / 1000,
-                    ))?;
-                    Ok(py_dt.unbind())
-                }
-                None => Ok(py.None()),
+            let dt = from_raw::<NaiveDateTime>(pg_type, raw)?;
+            if opts.timestamp_output != TimestampOutput::DateTime {
+                // Naive timestamps are interpreted as UTC
+                return Ok(opts.timestamp_output.epoch_to_pyobject(py, dt.and_utc().timestamp_micros()));
             }
+            let datetime = py.import_bound("datetime")?;
+            let py_dt = datetime.getattr("datetime")?.call1((
+                dt.date().year(),
+                dt.date().month(),
+                dt.date().day(),
+                dt.time().hour(),
+                dt.time().minute(),
+                dt.time().second(),
+                dt.time().nanosecond() / 1000,
+            ))?;
+            Ok(py_dt.unbind())
         }
         Type::TIMESTAMPTZ => {
-            let v: Option<DateTime<Utc>> = row.get(idx);
-            match v {
-                Some(dt) => {
-                    let datetime_mod = py.import_bound("datetime")?;
-                    // Create datetime with timezone using fromisoformat
-                    let py_dt = datetime_mod.getattr("datetime")?.call_method1(
-                        "fromisoformat",
-                        (dt.format("%Y-%m-%dT%H:%M:%S+00:00").to_string(),),
-                    )?;
-                    Ok(py_dt.unbind())
+            let dt = from_raw::<DateTime<Utc>>(pg_type, raw)?;
+            if opts.timestamp_output != TimestampOutput::DateTime {
+                return Ok(opts.timestamp_output.epoch_to_pyobject(py, dt.timestamp_micros()));
+            }
+            let datetime_mod = py.import_bound("datetime")?;
+            // Create datetime with timezone using fromisoformat
+            let py_dt = datetime_mod.getattr("datetime")?.call_method1(
+                "fromisoformat",
+                (dt.format("%Y-%m-%dT%H:%M:%S%.6f+00:00").to_string(),),
+            )?;
+            Ok(py_dt.unbind())
+        }
+        _ if matches!(pg_type.kind(), Kind::Enum(_)) => {
+            let label = std::str::from_utf8(raw)
+                .map_err(|e| PyValueError::new_err(format!("Invalid label for enum {}: {}", pg_type, e)))?;
+            match adapters::enum_member(py, pg_type.name(), label)? {
+                Some(member) => Ok(member),
+                None => Ok(label.to_object(py)),
+            }
+        }
+        _ => match pg_type.kind() {
+            Kind::Array(member) => array_to_pyobject(py, member, raw, opts),
+            // Fallback: text-like types as strings, anything else as None
+            _ if <&str as FromSql>::accepts(pg_type) => Ok(from_raw::<&str>(pg_type, raw)?.to_object(py)),
+            _ => Ok(py.None()),
+        },
+    }
+}
+
+/// Dimension lengths, outermost first, and elements in row-major order (None for NULL)
+type ArrayParts<'a> = (Vec<usize>, Vec<Option<&'a [u8]>>);
+
+/// Split a binary array value into its dimensions and elements
+fn parse_array(raw: &[u8]) -> Option<ArrayParts<'_>> {
+    let mut buf = raw;
+    let read_i32 = |buf: &mut &[u8]| {
+        let (head, rest) = buf.split_first_chunk::<4>()?;
+        *buf = rest;
+        Some(i32::from_be_bytes(*head))
+    };
+    let ndim = usize::try_from(read_i32(&mut buf)?).ok()?;
+    // The has-NULLs flag and the element type OID
+    read_i32(&mut buf)?;
+    read_i32(&mut buf)?;
+    // Each dimension's length and lower bound; lists always start at 0
+    let mut dims = Vec::with_capacity(ndim);
+    for _ in 0..ndim {
+        dims.push(usize::try_from(read_i32(&mut buf)?).ok()?);
+        read_i32(&mut buf)?;
+    }
+    let count = if dims.is_empty() { 0 } else { dims.iter().product() };
+    let mut elements = Vec::with_capacity(count);
+    for _ in 0..count {
+        match read_i32(&mut buf)? {
+            -1 => elements.push(None),
+            len => {
+                let len = usize::try_from(len).ok()?;
+                if len > buf.len() {
+                    return None;
                 }
-                None => Ok(py.None()),
+                let (element, rest) = buf.split_at(len);
+                buf = rest;
+                elements.push(Some(element));
+            }
+        }
+    }
+    Some((dims, elements))
+}
+
+/// Decode a binary array value into lists nested one level per dimension
+fn array_to_pyobject(py: Python<'_>, member: &Type, raw: &[u8], opts: &DecodeOptions) -> PyResult<PyObject> {
+    let (dims, elements) = parse_array(raw)
+        .ok_or_else(|| PyValueError::new_err(format!("Invalid binary value for {}[] ({} bytes)", member, raw.len())))?;
+    let mut values = elements.into_iter().map(|element| match element {
+        Some(raw) => adapters::convert(py, member.oid(), decode_value(py, member, raw, opts)?),
+        None => Ok(py.None()),
+    });
+    nest(py, &dims, &mut values)
+}
+
+/// Group `values` into nested lists with the lengths in `dims`, outermost first
+fn nest(py: Python<'_>, dims: &[usize], values: &mut impl Iterator<Item = PyResult<PyObject>>) -> PyResult<PyObject> {
+    let list = PyList::empty_bound(py);
+    match dims {
+        [] => {}
+        [len] => {
+            for value in values.take(*len) {
+                list.append(value?)?;
             }
         }
-        _ => {
-            // Fallback: try to get as string
-            let v: Option<String> = row.try_get(idx).ok().flatten();
-            Ok(v.map(|s| s.to_object(py)).unwrap_or_else(|| py.None()))
+        [len, inner @ ..] => {
+            for _ in 0..*len {
+                list.append(nest(py, inner, values)?)?;
+            }
         }
     }
+    Ok(list.into_any().unbind())
 }
 
 // Implement serde Serialize for PyValue (needed for List conversion)
@@ -235,15 +1038,19 @@ impl serde::Serialize for PyValue {
         S: serde::Serializer,
     {
         match self {
-            PyValue::None => serializer.serialize_none(),
+            PyValue::None | PyValue::TypedNull(_) => serializer.serialize_none(),
             PyValue::Bool(b) => serializer.serialize_bool(*b),
             PyValue::Int(i) => serializer.serialize_i64(*i),
+            PyValue::Float(f) if !f.is_finite() => Err(serde::ser::Error::custom(format!("Cannot convert {} to a JSON number", f))),
             PyValue::Float(f) => serializer.serialize_f64(*f),
             PyValue::String(s) => serializer.serialize_str(s),
             PyValue::Bytes(b) => serializer.serialize_bytes(b),
             PyValue::Uuid(u) => serializer.serialize_str(&u.to_string()),
+            PyValue::Decimal(d) => serializer.serialize_str(&d.to_string()),
+            PyValue::BigInt(digits) => digits.parse::<serde_json::Number>().map_err(serde::ser::Error::custom)?.serialize(serializer),
             PyValue::Json(j) => j.serialize(serializer),
             PyValue::Date(d) => serializer.serialize_str(&d.to_string()),
+            PyValue::Time(t) => serializer.serialize_str(&t.to_string()),
             PyValue::DateTime(dt) => serializer.serialize_str(&dt.to_string()),
             PyValue::DateTimeUtc(dt) => serializer.serialize_str(&dt.to_string()),
             PyValue::List(l) => l.serialize(serializer),
@@ -0,0 +1,86 @@
+//! `INSERT ... ON CONFLICT ... DO UPDATE` statements for `upsert()`
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+
+use crate::quote::quote_identifier;
+
+/// `table` with each dot-separated part quoted, so `"app.users"` names table users in schema app
+fn quote_table(table: &str) -> PyResult<String> {
+    let parts = table.split('.').map(quote_identifier).collect::<PyResult<Vec<_>>>()?;
+    Ok(parts.join("."))
+}
+
+fn quote_list(names: &[String]) -> PyResult<String> {
+    let quoted = names.iter().map(|name| quote_identifier(name)).collect::<PyResult<Vec<_>>>()?;
+    Ok(quoted.join(", "))
+}
+
+/// The statement inserting `rows` rows of `columns`, numbered `$1, $2, ...` row by row
+///
+/// On a conflict over `conflict_columns` the row's `update_columns`, which must be
+/// inserted columns, are set from `EXCLUDED`; they default to every inserted
+/// column outside the conflict target.
+/// With none left to update the conflicting row is skipped (`DO NOTHING`).
+/// With `returning`, every inserted or updated row is returned.
+pub(crate) fn upsert_statement(
+    table: &str,
+    columns: &[String],
+    conflict_columns: &[String],
+    update_columns: Option<&[String]>,
+    rows: usize,
+    returning: bool,
+) -> PyResult<String> {
+    if columns.is_empty() {
+        return Err(PyValueError::new_err("upsert needs at least one column"));
+    }
+    if conflict_columns.is_empty() {
+        return Err(PyValueError::new_err("upsert needs at least one conflict column"));
+    }
+    // EXCLUDED holds the default for a column that isn't inserted, so updating
+    // it on a conflict would overwrite the stored value with that default
+    if let Some(missing) = update_columns.unwrap_or_default().iter().find(|name| !columns.contains(name)) {
+        return Err(PyValueError::new_err(format!("update column '{}' is not among the inserted columns", missing)));
+    }
+    let update: Vec<&String> = match update_columns {
+        Some(names) => names.iter().collect(),
+        None => columns.iter().filter(|name| !conflict_columns.contains(name)).collect(),
+    };
+
+    let values = (0..rows)
+        .map(|row| {
+            let placeholders = (1..=columns.len())
+                .map(|column| format!("${}", row * columns.len() + column))
+                .collect::<Vec<_>>();
+            format!("({})", placeholders.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let action = match update.is_empty() {
+        true => "DO NOTHING".to_string(),
+        false => {
+            let assignments = update.iter()
+                .map(|name| quote_identifier(name).map(|quoted| format!("{0} = EXCLUDED.{0}", quoted)))
+                .collect::<PyResult<Vec<_>>>()?;
+            format!("DO UPDATE SET {}", assignments.join(", "))
+        }
+    };
+    Ok(format!(
+        "INSERT INTO {} ({}) VALUES {} ON CONFLICT ({}) {}{}",
+        quote_table(table)?, quote_list(columns)?, values, quote_list(conflict_columns)?, action,
+        if returning { " RETURNING *" } else { "" },
+    ))
+}
+
+/// Rows that fit in one statement without passing the protocol's 65535 bind parameters
+pub(crate) fn rows_per_statement(columns: usize) -> usize {
+    (usize::from(u16::MAX) / columns.max(1)).max(1)
+}
+
+/// Check that a row has one value per column
+pub(crate) fn check_row(columns: &[String], values: usize) -> PyResult<()> {
+    match values == columns.len() {
+        true => Ok(()),
+        false => Err(PyValueError::new_err(format!("upsert got {} values for {} columns", values, columns.len()))),
+    }
+}